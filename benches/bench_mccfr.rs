@@ -3,7 +3,10 @@ extern crate gtcogs;
 extern crate rand;
 extern crate test;
 
-use gtcogs::{goofspiel, Goofspiel, OuterMCCFR, TreeGame};
+use gtcogs::{
+    goofspiel, simulate, ActionIndex, FlatCFR, FlatTree, Game, Goofspiel, LazyTreeGame, OuterMCCFR,
+    Pruning, TreeGame, UniformStrategy,
+};
 use rand::{rngs::SmallRng, SeedableRng};
 use test::Bencher;
 
@@ -40,3 +43,66 @@ fn bench_os_mccfr_goofspiel5_tree(b: &mut Bencher) {
     let mut rng = SmallRng::seed_from_u64(4);
     b.iter(|| mc.compute_rng(1, 0.6, &mut rng));
 }
+
+#[bench]
+fn bench_os_mccfr_goofspiel5_flat(b: &mut Bencher) {
+    let g = Goofspiel::new(5, goofspiel::Scoring::ZeroSum);
+    let mut mc = FlatCFR::new(FlatTree::from_game(&g));
+    let mut rng = SmallRng::seed_from_u64(5);
+    b.iter(|| mc.compute_sampled_rng(1, 0.6, &mut rng));
+}
+
+#[bench]
+fn bench_cfr_goofspiel4_flat(b: &mut Bencher) {
+    let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+    let mut cfr = FlatCFR::new(FlatTree::from_game(&g));
+    b.iter(|| cfr.compute(1));
+}
+
+#[bench]
+fn bench_cfr_goofspiel4_flat_pruned(b: &mut Bencher) {
+    let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+    let mut cfr = FlatCFR::new(FlatTree::from_game(&g)).with_pruning(Pruning::new(-1.0, 10, 20));
+    b.iter(|| cfr.compute(1));
+}
+
+#[bench]
+fn bench_os_mccfr_goofspiel5_lazy(b: &mut Bencher) {
+    let g = Goofspiel::new(5, goofspiel::Scoring::ZeroSum);
+    let mut mc = OuterMCCFR::new(LazyTreeGame::with_max_nodes(g, 1_000_000));
+    let mut rng = SmallRng::seed_from_u64(6);
+    b.iter(|| mc.compute_rng(1, 0.6, &mut rng));
+}
+
+/// Action indices of uniformly random plays, for the replay benches.
+fn random_plays<G: Game>(g: &G, count: usize) -> Vec<Vec<ActionIndex>> {
+    let u = UniformStrategy {};
+    let mut rng = SmallRng::seed_from_u64(7);
+    (0..count)
+        .map(|_| simulate(g, &[&u, &u], &mut rng).actions().to_vec())
+        .collect()
+}
+
+fn replay<G: Game>(g: &G, plays: &[Vec<ActionIndex>]) {
+    for p in plays {
+        let h = p
+            .iter()
+            .fold(g.start(), |h, &ai| g.play_owned(h, ai as usize));
+        test::black_box(h);
+    }
+}
+
+#[bench]
+fn bench_replay_goofspiel5(b: &mut Bencher) {
+    let g = Goofspiel::new(5, goofspiel::Scoring::ZeroSum);
+    let plays = random_plays(&g, 100);
+    b.iter(|| replay(&g, &plays));
+}
+
+#[bench]
+fn bench_replay_goofspiel5_lazy(b: &mut Bencher) {
+    let g = LazyTreeGame::new(Goofspiel::new(5, goofspiel::Scoring::ZeroSum));
+    let plays = random_plays(&g, 100);
+    replay(&g, &plays);
+    b.iter(|| replay(&g, &plays));
+}
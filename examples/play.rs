@@ -0,0 +1,218 @@
+//! Play a game against an outer sampling MCCFR agent in the terminal.
+//!
+//! Usage: `cargo run --release --example play -- [game] [--seat N] [--iterations N] [--seed N]
+//! [--load FILE] [--save FILE]`
+//!
+//! The game is one of `GAMES` (`goofspiel4` by default). The agent is trained for
+//! `--iterations` iterations (20000 by default) unless `--load` reads its strategy table
+//! from a file written by `--save` (the JSON lines format of `TableSnapshot`).
+
+extern crate gtcogs;
+extern crate rand;
+
+use gtcogs::{
+    goofspiel, ActivePlayer, Blotto, Game, Goofspiel, LiarsDice, Observation, OshiZumo, OuterMCCFR,
+    PlayerObservation, Sequentialized, Solver, Strategy, TableSnapshot,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// Command line options (besides the game).
+struct Options {
+    seat: usize,
+    iterations: usize,
+    seed: u64,
+    load: Option<String>,
+    save: Option<String>,
+}
+
+type Runner = fn(&Options) -> Result<(), String>;
+
+/// The playable games: name, description and how to run them.
+const GAMES: &[(&str, &str, Runner)] = &[
+    ("goofspiel3", "Goofspiel with 3 cards", |o| {
+        run(Goofspiel::new(3, goofspiel::Scoring::ZeroSum), o)
+    }),
+    ("goofspiel4", "Goofspiel with 4 cards", |o| {
+        run(Goofspiel::new(4, goofspiel::Scoring::ZeroSum), o)
+    }),
+    ("goofspiel5", "Goofspiel with 5 cards", |o| {
+        run(Goofspiel::new(5, goofspiel::Scoring::ZeroSum), o)
+    }),
+    (
+        "liars_dice",
+        "Liar's Dice with one six-sided die each",
+        |o| run(LiarsDice::new(vec![1, 1], 6), o),
+    ),
+    ("oshi_zumo", "Oshi-Zumo with 5 coins on 5 positions", |o| {
+        run(OshiZumo::new(5, 2, 1, 10), o)
+    }),
+    (
+        "blotto",
+        "Colonel Blotto with 5 soldiers on 3 fields",
+        |o| run(Sequentialized::new(Blotto::new(5, 3)), o),
+    ),
+];
+
+/// Train or load the agent and play one game against it.
+fn run<G: Game>(game: G, o: &Options) -> Result<(), String> {
+    if o.seat >= game.players() {
+        return Err(format!(
+            "seat {} out of range, the game has {} players",
+            o.seat,
+            game.players()
+        ));
+    }
+    let mut rng = SmallRng::seed_from_u64(o.seed);
+    let table = match o.load {
+        Some(ref path) => {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            TableSnapshot::read(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => {
+            println!(
+                "Training the agent for {} iterations (seed {}) ...",
+                o.iterations, o.seed
+            );
+            let mut mc = OuterMCCFR::new(game.clone());
+            mc.compute_rng(o.iterations, 0.6, &mut rng);
+            mc.table_snapshot()
+        }
+    };
+    if let Some(ref path) = o.save {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        table
+            .write(BufWriter::new(file))
+            .map_err(|e| format!("{}: {}", path, e))?;
+        println!("Strategy saved to {}", path);
+    }
+    let agents = vec![&table as &dyn Strategy<G>; game.players()];
+    println!("You are player {}.", o.seat);
+    if play_interactive(&game, o.seat, &agents, &mut rng).is_none() {
+        println!();
+    }
+    Ok(())
+}
+
+/// Play one game of `game` with a human in seat `human` and `agents` (indexed by seat,
+/// the human seat is ignored) in the others. Returns the final payoffs, `None` when the
+/// input ends before the game does.
+fn play_interactive<G: Game, R: Rng>(
+    game: &G,
+    human: usize,
+    agents: &[&dyn Strategy<G>],
+    rng: &mut R,
+) -> Option<Vec<f64>> {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut hist = game.start();
+    let mut seen = 0;
+    loop {
+        let ai = match hist.active {
+            ActivePlayer::Terminal(ref payoffs) => {
+                print_observations::<G>(&hist.observations[human][seen..]);
+                println!("Game over, payoffs: {:?}", payoffs);
+                return Some(payoffs.clone());
+            }
+            ActivePlayer::Chance(ref dist) => dist.sample_idx_rng(rng),
+            ActivePlayer::Player(p, ref actions) if p as usize == human => {
+                print_observations::<G>(&hist.observations[human][seen..]);
+                seen = hist.observations[human].len();
+                for (i, a) in actions.iter().enumerate() {
+                    println!("  [{}] {:?}", i, a);
+                }
+                loop {
+                    print!("Your action: ");
+                    io::stdout().flush().unwrap();
+                    let line = lines.next()?.ok()?;
+                    match line.trim().parse::<usize>() {
+                        Ok(i) if i < actions.len() => break i,
+                        _ => println!("Enter a number between 0 and {}", actions.len() - 1),
+                    }
+                }
+            }
+            ActivePlayer::Player(p, _) => {
+                let p = p as usize;
+                let policy = agents[p].policy(&hist.active, &hist.observations[p]);
+                *policy.sample_ref_rng(rng) as usize
+            }
+        };
+        hist = game.play_owned(hist, ai);
+    }
+}
+
+fn print_observations<G: Game>(obs: &[PlayerObservation<G>]) {
+    for o in obs {
+        match o {
+            Observation::Own(a) => println!("You played {:?}", a),
+            Observation::Obs(o) => println!("You observe {:?}", o),
+        }
+    }
+}
+
+fn usage() -> String {
+    let mut s = String::from(
+        "Usage: play [game] [--seat N] [--iterations N] [--seed N] [--load FILE] [--save FILE]\n\
+         Games:\n",
+    );
+    for (name, description, _) in GAMES {
+        s.push_str(&format!("  {:12} {}\n", name, description));
+    }
+    s
+}
+
+/// Parse the command line into the game name and the options.
+fn parse_args() -> Result<(String, Options), String> {
+    let mut game = None;
+    let mut o = Options {
+        seat: 0,
+        iterations: 20000,
+        seed: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        load: None,
+        save: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--seat" => o.seat = number(&value()?)?,
+            "--iterations" => o.iterations = number(&value()?)?,
+            "--seed" => o.seed = number(&value()?)?,
+            "--load" => o.load = Some(value()?),
+            "--save" => o.save = Some(value()?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if game.is_none() => game = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok((game.unwrap_or_else(|| "goofspiel4".into()), o))
+}
+
+fn number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("{} is not a number", s))
+}
+
+fn main() {
+    let (name, options) = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprint!("{}\n{}", e, usage());
+            std::process::exit(2);
+        }
+    };
+    let runner = match GAMES.iter().find(|g| g.0 == name) {
+        Some(g) => g.2,
+        None => {
+            eprint!("unknown game {}\n{}", name, usage());
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = runner(&options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
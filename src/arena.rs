@@ -0,0 +1,191 @@
+use crate::{playout, Game, Strategy, Utility};
+use rand::Rng;
+use std::fmt;
+
+/// Round-robin tournament of named strategies in a two-player game.
+///
+/// Only two-player games are supported, `new` panics for other player counts.
+pub struct Arena<'a, G: Game> {
+    pub game: G,
+    pub agents: Vec<(String, &'a dyn Strategy<G>)>,
+}
+
+/// Mean payoff of an agent against an opponent with its 95% confidence interval half-width.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchStats {
+    pub games: usize,
+    pub mean: Utility,
+    pub ci95: Utility,
+    /// Wins, draws and losses by comparing the two payoffs.
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    /// `matches[i][j]`: payoff statistics of agent `i` against agent `j` (`None` on the diagonal).
+    pub matches: Vec<Vec<Option<MatchStats>>>,
+    /// Elo ratings (mean 0) fitted to the wins, draws and losses.
+    pub elo: Vec<f64>,
+}
+
+impl<'a, G: Game> Arena<'a, G> {
+    pub fn new(game: G) -> Self {
+        assert_eq!(game.players(), 2, "arena requires a two-player game");
+        Arena {
+            game,
+            agents: Vec::new(),
+        }
+    }
+
+    pub fn add<S: Into<String>>(&mut self, name: S, strategy: &'a dyn Strategy<G>) {
+        self.agents.push((name.into(), strategy));
+    }
+
+    /// Play every pair of agents for `playouts` (at least one) games in each seating.
+    pub fn round_robin<R: Rng>(&self, playouts: usize, rng: &mut R) -> TournamentResult {
+        assert!(playouts > 0, "round robin needs at least one playout");
+        let n = self.agents.len();
+        let mut matches = vec![vec![None; n]; n];
+        let pairs = (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j)));
+        for (i, j) in pairs {
+            let mut payoffs = Vec::with_capacity(2 * playouts);
+            for _ in 0..playouts {
+                let u = playout(&self.game, &[self.agents[i].1, self.agents[j].1], rng);
+                payoffs.push((u[0], u[1]));
+                let u = playout(&self.game, &[self.agents[j].1, self.agents[i].1], rng);
+                payoffs.push((u[1], u[0]));
+            }
+            matches[i][j] = Some(MatchStats::new(&payoffs));
+            let swapped: Vec<_> = payoffs.iter().map(|&(a, b)| (b, a)).collect();
+            matches[j][i] = Some(MatchStats::new(&swapped));
+        }
+        let elo = fit_elo(&matches);
+        TournamentResult {
+            names: self.agents.iter().map(|a| a.0.clone()).collect(),
+            matches,
+            elo,
+        }
+    }
+}
+
+impl MatchStats {
+    /// Statistics from pairs of (own payoff, opponent payoff), at least one.
+    fn new(payoffs: &[(Utility, Utility)]) -> Self {
+        assert!(!payoffs.is_empty(), "match statistics of no games");
+        let n = payoffs.len() as f64;
+        let mean = payoffs.iter().map(|p| p.0).sum::<f64>() / n;
+        let var = payoffs.iter().map(|p| (p.0 - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+        MatchStats {
+            games: payoffs.len(),
+            mean,
+            ci95: 1.96 * (var / n).sqrt(),
+            wins: payoffs.iter().filter(|p| p.0 > p.1).count(),
+            draws: payoffs.iter().filter(|p| p.0 == p.1).count(),
+            losses: payoffs.iter().filter(|p| p.0 < p.1).count(),
+        }
+    }
+
+    /// Wins plus half of the draws.
+    fn score(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+}
+
+/// Fit Bradley-Terry strengths with the MM algorithm and convert them to Elo.
+/// One virtual draw is added to every pair so that unbeaten agents get finite ratings.
+fn fit_elo(matches: &[Vec<Option<MatchStats>>]) -> Vec<f64> {
+    let n = matches.len();
+    let mut gamma = vec![1.0; n];
+    for _ in 0..1000 {
+        for i in 0..n {
+            let mut score = 0.0;
+            let mut denom = 0.0;
+            for j in 0..n {
+                if let Some(ref m) = matches[i][j] {
+                    score += m.score() + 0.5;
+                    denom += (m.games + 1) as f64 / (gamma[i] + gamma[j]);
+                }
+            }
+            if denom > 0.0 {
+                gamma[i] = score / denom;
+            }
+        }
+    }
+    let elo: Vec<f64> = gamma.iter().map(|g| 400.0 * g.log10()).collect();
+    let mean = elo.iter().sum::<f64>() / n.max(1) as f64;
+    elo.iter().map(|e| e - mean).collect()
+}
+
+impl fmt::Display for TournamentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let w = self
+            .names
+            .iter()
+            .map(|n| n.len())
+            .max()
+            .unwrap_or(0)
+            .max(16);
+        write!(f, "{:w$}", "", w = w)?;
+        for n in &self.names {
+            write!(f, " {:>w$}", n, w = w)?;
+        }
+        writeln!(f, " {:>8}", "Elo")?;
+        for (i, n) in self.names.iter().enumerate() {
+            write!(f, "{:w$}", n, w = w)?;
+            for m in &self.matches[i] {
+                match m {
+                    Some(m) => write!(
+                        f,
+                        " {:>w$}",
+                        format!("{:.3} ± {:.3}", m.mean, m.ci95),
+                        w = w
+                    )?,
+                    None => write!(f, " {:>w$}", "-", w = w)?,
+                }
+            }
+            writeln!(f, " {:>8.1}", self.elo[i])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Arena;
+    use crate::{goofspiel, Goofspiel, OuterMCCFR, UniformStrategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_arena_goofspiel() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut mc = OuterMCCFR::new(g.clone());
+        mc.compute_rng(2000, 0.6, &mut rng);
+        let u = UniformStrategy {};
+        let mut arena = Arena::new(g);
+        arena.add("uniform", &u);
+        arena.add("mccfr", &mc);
+        let res = arena.round_robin(500, &mut rng);
+        let m = res.matches[1][0].clone().unwrap();
+        assert_eq!(m.games, 1000);
+        assert!(m.mean > 0.0);
+        assert!((m.mean + res.matches[0][1].clone().unwrap().mean).abs() < 1e-9);
+        assert!(res.elo[1] > res.elo[0]);
+        assert!(res.elo.iter().sum::<f64>().abs() < 1e-6);
+        assert!(format!("{}", res).contains("mccfr"));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one playout")]
+    fn test_no_playouts() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        let mut arena = Arena::new(g);
+        arena.add("a", &u);
+        arena.add("b", &u);
+        arena.round_robin(0, &mut SmallRng::seed_from_u64(1));
+    }
+}
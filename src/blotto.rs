@@ -0,0 +1,97 @@
+use crate::{SimultaneousGame, Stage, Utility};
+
+/// Colonel Blotto, a one-shot simultaneous allocation game.
+///
+/// Both players split `soldiers` soldiers among `fields` battlefields (an action is the
+/// number of soldiers per field) and a field is won by the player with more soldiers on it.
+/// The player winning more fields gets utility 1 and the other -1 (0 for equal counts). Both
+/// allocations are revealed at the end. Use `Sequentialized` to play it as a `Game`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blotto {
+    pub soldiers: u32,
+    pub fields: usize,
+    /// All the allocations of the soldiers.
+    allocations: Vec<Vec<u32>>,
+}
+
+impl Blotto {
+    pub fn new(soldiers: u32, fields: usize) -> Self {
+        assert!(fields >= 1, "Blotto needs at least one field");
+        let mut allocations = Vec::new();
+        allocate(soldiers, fields, &mut Vec::new(), &mut allocations);
+        Blotto {
+            soldiers,
+            fields,
+            allocations,
+        }
+    }
+
+    /// Utilities of the two allocations.
+    pub fn utilities(&self, a: &[u32], b: &[u32]) -> Vec<Utility> {
+        let won = a
+            .iter()
+            .zip(b)
+            .map(|(x, y)| (x > y) as i32 - (y > x) as i32);
+        let d = won.sum::<i32>().signum() as Utility;
+        vec![d, -d]
+    }
+}
+
+/// Push all splits of `soldiers` into `fields` fields extending `prefix` to `out`.
+fn allocate(soldiers: u32, fields: usize, prefix: &mut Vec<u32>, out: &mut Vec<Vec<u32>>) {
+    if fields == 1 {
+        prefix.push(soldiers);
+        out.push(prefix.clone());
+        prefix.pop();
+        return;
+    }
+    for s in 0..=soldiers {
+        prefix.push(s);
+        allocate(soldiers - s, fields - 1, prefix, out);
+        prefix.pop();
+    }
+}
+
+impl SimultaneousGame for Blotto {
+    type State = ();
+    /// Both allocations.
+    type Observation = Vec<Vec<u32>>;
+    type Action = Vec<u32>;
+
+    fn players(&self) -> usize {
+        2
+    }
+
+    fn initial_state(&self) -> (Self::State, Stage<Self>) {
+        ((), Stage::Players(vec![self.allocations.clone(); 2]))
+    }
+
+    fn update_state(
+        &self,
+        _state: &Self::State,
+        joint: &[Self::Action],
+    ) -> (Self::State, Stage<Self>, Vec<Option<Self::Observation>>) {
+        let u = self.utilities(&joint[0], &joint[1]);
+        ((), Stage::Terminal(u), vec![Some(joint.to_vec()); 3])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Blotto;
+    use crate::{exploitability, ActivePlayer, Game, OuterMCCFR, Sequentialized};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_blotto() {
+        let g = Sequentialized::new(Blotto::new(4, 3));
+        let h = g.start();
+        assert_eq!(h.active.actions().len(), 15);
+        let h = g.play_value(&g.play_value(&h, &vec![2, 2, 0]), &vec![1, 1, 2]);
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![1.0, -1.0]));
+
+        let mut mc = OuterMCCFR::new(g.clone());
+        mc.compute_rng(5000, 0.6, &mut SmallRng::seed_from_u64(1));
+        assert!(exploitability(&g, &mc) < 0.3);
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits of a solver run. The run stops when any of the set limits is reached or the stop
+/// flag is raised; the limits are checked between iterations, so a run always ends in a
+/// consistent state and can be resumed by another run.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    pub iterations: Option<usize>,
+    pub time: Option<Duration>,
+    /// Nodes traversed within the run.
+    pub nodes: Option<usize>,
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    /// No limits, only the stop flag (if set) ends the run.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn iterations(iterations: usize) -> Self {
+        Self::default().with_iterations(iterations)
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self::default().with_time(time)
+    }
+
+    pub fn nodes(nodes: usize) -> Self {
+        Self::default().with_nodes(nodes)
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = Some(iterations);
+        self
+    }
+
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Stop the run once `stop` is set (e.g. from another thread or a signal handler).
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Whether a run started at `start` with the given progress should stop.
+    pub fn exhausted(&self, start: Instant, iterations: usize, nodes: usize) -> bool {
+        self.iterations.is_some_and(|i| iterations >= i)
+            || self.nodes.is_some_and(|n| nodes >= n)
+            || self.time.is_some_and(|t| start.elapsed() >= t)
+            || self
+                .stop
+                .as_ref()
+                .is_some_and(|s| s.load(Ordering::Relaxed))
+    }
+}
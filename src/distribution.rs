@@ -1,8 +1,11 @@
 use rand::{
     distributions::{Distribution, WeightedIndex},
-    thread_rng, Rng,
+    Rng,
 };
 
+/// A finite distribution over items.
+///
+/// All sampling methods take an explicit RNG, see the crate documentation on reproducibility.
 #[derive(Clone, Debug)]
 pub struct Categorical<T>(Vec<f64>, WeightedIndex<f64>, Vec<T>);
 
@@ -14,17 +17,17 @@ impl<T: PartialEq> PartialEq for Categorical<T> {
 
 impl<T> Categorical<T> {
     #[inline]
-    pub fn items<'a>(&'a self) -> &'a Vec<T> {
+    pub fn items(&self) -> &Vec<T> {
         &self.2
     }
 
     #[inline]
-    pub fn probs<'a>(&'a self) -> &'a Vec<f64> {
+    pub fn probs(&self) -> &Vec<f64> {
         &self.0
     }
 
     #[inline]
-    pub fn sample_idx_rng<'a, R: Rng>(&'a self, rng: &mut R) -> usize {
+    pub fn sample_idx_rng<R: Rng>(&self, rng: &mut R) -> usize {
         self.1.sample(rng)
     }
 
@@ -33,11 +36,6 @@ impl<T> Categorical<T> {
         &self.2[self.1.sample(rng)]
     }
 
-    #[inline]
-    pub fn sample_ref<'a>(&'a self) -> &'a T {
-        self.sample_ref_rng(&mut thread_rng())
-    }
-
     #[inline]
     pub fn rample_ref_pair_rng<'a, R: Rng>(&'a self, rng: &mut R) -> (f64, &'a T) {
         let idx = self.1.sample(rng);
@@ -54,7 +52,7 @@ impl<T> Categorical<T> {
         let mut ps: Vec<f64> = probs.into();
         let s: f64 = ps.iter().sum();
         ps.iter_mut().for_each(|p| {
-            *p = *p / s;
+            *p /= s;
         });
         Self::new(ps, items)
     }
@@ -83,12 +81,7 @@ impl<T: Clone> Categorical<T> {
     }
 
     #[inline]
-    pub fn sample(&self) -> T {
-        self.sample_rng(&mut thread_rng())
-    }
-
-    #[inline]
-    pub fn sample_pair_rng<'a, R: Rng>(&'a self, rng: &mut R) -> (f64, T) {
+    pub fn sample_pair_rng<R: Rng>(&self, rng: &mut R) -> (f64, T) {
         let idx = self.1.sample(rng);
         (self.0[idx], self.2[idx].clone())
     }
@@ -103,5 +96,5 @@ pub fn sample_weighted<R: Rng>(ps: &[f64], rng: &mut R) -> usize {
             return i;
         }
     }
-    return ps.len() - 1;
+    ps.len() - 1
 }
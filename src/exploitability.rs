@@ -0,0 +1,181 @@
+use crate::{ActivePlayer, Game, HistoryInfo, PlayerObservation, Strategy, Utility};
+use hashbrown::HashMap;
+
+/// A node of the game tree as seen by the best-responding player.
+enum Node {
+    Terminal(Utility),
+    /// Chance or another player: the children reached with a positive probability.
+    Fixed(Vec<(f64, usize)>),
+    /// Decision of the responding player in the given information set.
+    Respond(usize, Vec<usize>),
+}
+
+/// The game tree of one best-responding player with the reach probabilities of its
+/// decision nodes, and the actions chosen in its information sets.
+struct BestResponse<G: Game> {
+    player: usize,
+    nodes: Vec<Node>,
+    infosets: HashMap<Vec<PlayerObservation<G>>, usize>,
+    /// Observation sequence length and decision nodes (with their reach by chance and the
+    /// other players) of every information set.
+    members: Vec<(usize, Vec<(usize, f64)>)>,
+    actions: Vec<usize>,
+}
+
+impl<G: Game> BestResponse<G> {
+    /// Add the subtree of `hist` (reached with probability `reach` by chance and the other
+    /// players) in pre-order, returning the index of its root.
+    fn build(
+        &mut self,
+        game: &G,
+        strategies: &[&dyn Strategy<G>],
+        hist: &HistoryInfo<G>,
+        reach: f64,
+    ) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(Node::Terminal(0.0));
+        let probs = match hist.active {
+            ActivePlayer::Terminal(ref u) => {
+                self.nodes[idx] = Node::Terminal(u[self.player]);
+                return idx;
+            }
+            ActivePlayer::Chance(ref d) => d.probs().clone(),
+            ActivePlayer::Player(p, _) if p as usize == self.player => {
+                let obs = &hist.observations[self.player];
+                let next = self.members.len();
+                let infoset = *self.infosets.entry(obs.clone()).or_insert(next);
+                if infoset == next {
+                    self.members.push((obs.len(), Vec::new()));
+                }
+                self.members[infoset].1.push((idx, reach));
+                let children = (0..hist.active.actions().len())
+                    .map(|a| self.build(game, strategies, &game.play(hist, a), reach))
+                    .collect();
+                self.nodes[idx] = Node::Respond(infoset, children);
+                return idx;
+            }
+            ActivePlayer::Player(p, _) => {
+                let p = p as usize;
+                let policy = strategies[p].policy(&hist.active, &hist.observations[p]);
+                let mut probs = vec![0.0; hist.active.actions().len()];
+                for (&a, q) in policy.items().iter().zip(policy.probs()) {
+                    probs[a as usize] += q;
+                }
+                probs
+            }
+        };
+        let children = probs
+            .iter()
+            .enumerate()
+            .filter(|(_, &q)| q > 0.0)
+            .map(|(a, &q)| {
+                let child = self.build(game, strategies, &game.play(hist, a), reach * q);
+                (q, child)
+            })
+            .collect();
+        self.nodes[idx] = Node::Fixed(children);
+        idx
+    }
+
+    /// Expected utility of `player` in node `n` with the decided best responses, memoized
+    /// in `values`.
+    fn value(&self, n: usize, values: &mut Vec<Option<Utility>>) -> Utility {
+        if let Some(v) = values[n] {
+            return v;
+        }
+        let v = match self.nodes[n] {
+            Node::Terminal(u) => u,
+            Node::Fixed(ref children) => children
+                .iter()
+                .map(|&(q, c)| q * self.value(c, values))
+                .sum(),
+            Node::Respond(infoset, ref children) => {
+                self.value(children[self.actions[infoset]], values)
+            }
+        };
+        values[n] = Some(v);
+        v
+    }
+}
+
+/// Utility of the best response of `player` against the other players' `strategies`
+/// (`strategies[player]` is ignored).
+///
+/// Traverses the whole tree once, requires perfect recall. Information sets are decided from
+/// the deepest (the longest observation sequence) up, so every decision already knows the
+/// best responses below it and every node value is computed only once.
+pub fn best_response_value<G: Game>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    player: usize,
+) -> Utility {
+    let mut br = BestResponse {
+        player,
+        nodes: Vec::new(),
+        infosets: HashMap::new(),
+        members: Vec::new(),
+        actions: Vec::new(),
+    };
+    br.build(game, strategies, &game.start(), 1.0);
+    br.actions = vec![0; br.members.len()];
+    let mut order: Vec<_> = (0..br.members.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(br.members[i].0));
+    let mut values = vec![None; br.nodes.len()];
+    for infoset in order {
+        let (_, ref members) = br.members[infoset];
+        let mut action_values: Vec<Utility> = Vec::new();
+        for &(n, reach) in members {
+            if let Node::Respond(_, ref children) = br.nodes[n] {
+                action_values.resize(children.len(), 0.0);
+                for (v, &c) in action_values.iter_mut().zip(children) {
+                    *v += reach * br.value(c, &mut values);
+                }
+            }
+        }
+        let best = (0..action_values.len())
+            .max_by(|&a, &b| action_values[a].total_cmp(&action_values[b]))
+            .unwrap_or(0);
+        br.actions[infoset] = best;
+    }
+    br.value(0, &mut values)
+}
+
+/// Exploitability of `strategy` played by both players of a two-player zero-sum game:
+/// the mean of the best response values against it (0 for a Nash equilibrium).
+pub fn exploitability<G: Game>(game: &G, strategy: &dyn Strategy<G>) -> Utility {
+    assert_eq!(
+        game.players(),
+        2,
+        "exploitability requires a two-player game"
+    );
+    let s = [strategy, strategy];
+    (best_response_value(game, &s, 0) + best_response_value(game, &s, 1)) / 2.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{best_response_value, exploitability};
+    use crate::{goofspiel, Goofspiel, MatrixGame, OuterMCCFR, Sequentialized, UniformStrategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_exploitability() {
+        let rps = Sequentialized::new(MatrixGame::zero_sum(&[
+            vec![0.0, -1.0, 1.0],
+            vec![1.0, 0.0, -1.0],
+            vec![-1.0, 1.0, 0.0],
+        ]));
+        assert!(exploitability(&rps, &UniformStrategy {}).abs() < 1e-9);
+        let skewed = Sequentialized::new(MatrixGame::zero_sum(&[vec![2.0, -1.0], vec![-1.0, 1.0]]));
+        let u = UniformStrategy {};
+        assert!((best_response_value(&skewed, &[&u, &u], 0) - 0.5).abs() < 1e-9);
+        assert!((best_response_value(&skewed, &[&u, &u], 1) - 0.0).abs() < 1e-9);
+
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let uniform = exploitability(&g, &UniformStrategy {});
+        let mut mc = OuterMCCFR::new(g.clone());
+        mc.compute_rng(2000, 0.6, &mut SmallRng::seed_from_u64(1));
+        let trained = exploitability(&g, &mc);
+        assert!(trained < uniform / 2.0);
+    }
+}
@@ -0,0 +1,581 @@
+use crate::mccfr::{regret_matching, RegretTables, SampledNode, SampledTree, Sampler};
+use crate::{
+    ActionIndex, ActivePlayer, Categorical, Exploration, Game, HistoryInfo, MCCFRConfig,
+    PlayerObservation, Solver, SolverSnapshot, SolverStats, Strategy, TableEntry, TableSnapshot,
+    Utility,
+};
+use hashbrown::HashMap;
+use rand::{Rng, RngCore};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Node kind marker for chance nodes in `FlatTree::player`.
+const CHANCE: u32 = u32::MAX - 1;
+/// Node kind marker for terminal nodes in `FlatTree::player`.
+const TERMINAL: u32 = u32::MAX;
+
+/// A node of a `FlatTree`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlatNode<'a> {
+    Player { player: usize, infoset: usize },
+    Chance,
+    Terminal(&'a [Utility]),
+}
+
+/// Compact copy of a game tree in flat arrays indexed by `u32` node ids.
+///
+/// Node 0 is the root and the children of every node are stored contiguously. Player nodes
+/// carry a precomputed information set id, so solvers can traverse the tree without
+/// building `HistoryInfo` or hashing observation sequences. Information sets are numbered
+/// across all players; their action slots are contiguous in `infoset_offsets` order.
+#[derive(Clone, Debug)]
+pub struct FlatTree<G: Game> {
+    players: usize,
+    /// Active player, `CHANCE` or `TERMINAL`.
+    player: Vec<u32>,
+    first_child: Vec<u32>,
+    num_children: Vec<u32>,
+    /// Probability of the node in its parent chance node, 1.0 below other nodes.
+    chance_prob: Vec<f64>,
+    /// Information set of player nodes, offset into `utilities` of terminal nodes.
+    data: Vec<u32>,
+    utilities: Vec<Utility>,
+    /// Offsets of the information sets' action slots, one extra at the end.
+    infoset_offsets: Vec<u32>,
+    infoset_player: Vec<u32>,
+    infoset_index: Vec<HashMap<Vec<PlayerObservation<G>>, u32>>,
+}
+
+impl<G: Game> FlatTree<G> {
+    /// Copy the whole tree of `game`.
+    pub fn from_game(game: &G) -> Self {
+        let mut t = FlatTree {
+            players: game.players(),
+            player: Vec::new(),
+            first_child: Vec::new(),
+            num_children: Vec::new(),
+            chance_prob: Vec::new(),
+            data: Vec::new(),
+            utilities: Vec::new(),
+            infoset_offsets: vec![0],
+            infoset_player: Vec::new(),
+            infoset_index: vec![HashMap::new(); game.players()],
+        };
+        t.push_nodes(1);
+        t.fill(game, 0, &game.start());
+        t
+    }
+
+    fn push_nodes(&mut self, n: usize) {
+        let len = self.player.len() + n;
+        self.player.resize(len, TERMINAL);
+        self.first_child.resize(len, 0);
+        self.num_children.resize(len, 0);
+        self.chance_prob.resize(len, 1.0);
+        self.data.resize(len, 0);
+    }
+
+    /// Fill in the node `node` for `hist` and recursively its children.
+    fn fill(&mut self, game: &G, node: usize, hist: &HistoryInfo<G>) {
+        let n = hist.active.actions().len();
+        match hist.active {
+            ActivePlayer::Terminal(ref u) => {
+                self.data[node] = self.utilities.len() as u32;
+                self.utilities.extend_from_slice(u);
+                return;
+            }
+            ActivePlayer::Chance(ref d) => {
+                self.player[node] = CHANCE;
+                let first = self.player.len();
+                self.push_nodes(n);
+                self.chance_prob[first..first + n].copy_from_slice(d.probs());
+            }
+            ActivePlayer::Player(p, _) => {
+                self.player[node] = p;
+                let next = self.infoset_player.len() as u32;
+                let id = *self.infoset_index[p as usize]
+                    .entry(hist.observations[p as usize].clone())
+                    .or_insert(next);
+                if id == next {
+                    self.infoset_player.push(p);
+                    let end = self.infoset_offsets[id as usize] + n as u32;
+                    self.infoset_offsets.push(end);
+                }
+                self.data[node] = id;
+                self.push_nodes(n);
+            }
+        }
+        let first = self.player.len() - n;
+        self.first_child[node] = first as u32;
+        self.num_children[node] = n as u32;
+        for i in 0..n {
+            self.fill(game, first + i, &game.play(hist, i));
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.player.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.player.is_empty()
+    }
+
+    pub fn node(&self, node: usize) -> FlatNode<'_> {
+        match self.player[node] {
+            CHANCE => FlatNode::Chance,
+            TERMINAL => {
+                let o = self.data[node] as usize;
+                FlatNode::Terminal(&self.utilities[o..o + self.players])
+            }
+            p => FlatNode::Player {
+                player: p as usize,
+                infoset: self.data[node] as usize,
+            },
+        }
+    }
+
+    /// Node ids of the children.
+    pub fn children(&self, node: usize) -> Range<usize> {
+        let f = self.first_child[node] as usize;
+        f..f + self.num_children[node] as usize
+    }
+
+    /// Probability of `node` in its parent chance node (1.0 below other nodes).
+    pub fn chance_prob(&self, node: usize) -> f64 {
+        self.chance_prob[node]
+    }
+
+    /// Number of information sets of all players.
+    pub fn infosets(&self) -> usize {
+        self.infoset_player.len()
+    }
+
+    pub fn infoset_player(&self, infoset: usize) -> usize {
+        self.infoset_player[infoset] as usize
+    }
+
+    /// Range of the information set's actions in per-action tables.
+    pub fn infoset_slots(&self, infoset: usize) -> Range<usize> {
+        self.infoset_offsets[infoset] as usize..self.infoset_offsets[infoset + 1] as usize
+    }
+
+    /// The information set of `player` with the given observation sequence.
+    pub fn infoset_of(&self, player: usize, obs: &[PlayerObservation<G>]) -> Option<usize> {
+        self.infoset_index[player].get(obs).map(|&i| i as usize)
+    }
+}
+
+/// Regret-based pruning of CFR iterations (`FlatCFR`, `OuterMCCFR`).
+///
+/// Actions of the updated player with cumulative regret below `threshold` (and zero
+/// probability in the current strategy) are skipped together with their subtrees, their
+/// regrets are not updated. Sampled iterations never explore pruned actions.
+/// Every `revisit`-th iteration traverses the whole tree again so pruned actions can recover.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pruning {
+    /// Negative regret threshold.
+    threshold: f64,
+    /// Iterations without pruning at the start.
+    warmup: usize,
+    /// Every `revisit`-th iteration is a full traversal (every iteration when 0 or 1).
+    revisit: usize,
+}
+
+impl Pruning {
+    pub fn new(threshold: f64, warmup: usize, revisit: usize) -> Self {
+        assert!(threshold <= 0.0, "pruning threshold must not be positive");
+        Pruning {
+            threshold,
+            warmup,
+            revisit,
+        }
+    }
+
+    /// The threshold to use in the given iteration, `None` for a full traversal.
+    pub(crate) fn threshold(&self, iteration: usize) -> Option<f64> {
+        if iteration < self.warmup || iteration % self.revisit.max(1) == 0 {
+            None
+        } else {
+            Some(self.threshold)
+        }
+    }
+}
+
+/// CFR solvers traversing a `FlatTree` directly.
+///
+/// `compute` runs full-tree vanilla CFR iterations (with alternating updates),
+/// `compute_sampled` runs outer sampling MCCFR iterations sharing the traversal of `OuterMCCFR`.
+/// Both accumulate into the same regret and average strategy tables.
+/// Both optionally use regret-based pruning (`with_pruning`).
+#[derive(Clone, Debug)]
+pub struct FlatCFR<G: Game> {
+    pub tree: FlatTree<G>,
+    pub iterations: usize,
+    pub nodes_traversed: usize,
+    /// Total time spent iterating.
+    pub elapsed: Duration,
+    pub pruning: Option<Pruning>,
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+impl<G: Game> FlatCFR<G> {
+    pub fn new(tree: FlatTree<G>) -> Self {
+        let slots = *tree.infoset_offsets.last().unwrap() as usize;
+        FlatCFR {
+            tree,
+            iterations: 0,
+            nodes_traversed: 0,
+            elapsed: Duration::default(),
+            pruning: None,
+            regrets: vec![0.0; slots],
+            strategy_sum: vec![0.0; slots],
+        }
+    }
+
+    pub fn with_pruning(mut self, pruning: Pruning) -> Self {
+        self.pruning = Some(pruning);
+        self
+    }
+
+    /// Current regret-matching strategy in the information set.
+    pub fn current_strategy(&self, infoset: usize) -> Vec<f64> {
+        regret_matching(&self.regrets[self.tree.infoset_slots(infoset)])
+    }
+
+    /// Average strategy in the information set.
+    pub fn average_strategy(&self, infoset: usize) -> Vec<f64> {
+        let s = &self.strategy_sum[self.tree.infoset_slots(infoset)];
+        let total = s.iter().sum::<f64>();
+        if total < 1e-6 {
+            vec![1.0 / s.len() as f64; s.len()]
+        } else {
+            s.iter().map(|v| v / total).collect()
+        }
+    }
+
+    pub fn compute(&mut self, iterations: usize) {
+        let start = Instant::now();
+        for _i in 0..iterations {
+            let prune = self.pruning.and_then(|p| p.threshold(self.iterations));
+            for player in 0..self.tree.players() {
+                self.cfr_rec(player, 0, 1.0, 1.0, prune);
+            }
+            self.iterations += 1;
+        }
+        self.elapsed += start.elapsed();
+    }
+
+    /// Returns the counterfactual value of `node` for `updated` (not weighted by `p_others`).
+    /// Prunes the updated player's actions with regret below `prune`.
+    fn cfr_rec(
+        &mut self,
+        updated: usize,
+        node: usize,
+        p_updated: f64,
+        p_others: f64,
+        prune: Option<f64>,
+    ) -> f64 {
+        self.nodes_traversed += 1;
+        match self.tree.node(node) {
+            FlatNode::Terminal(u) => u[updated],
+            FlatNode::Chance => self
+                .tree
+                .children(node)
+                .map(|c| {
+                    let p = self.tree.chance_prob(c);
+                    p * self.cfr_rec(updated, c, p_updated, p_others * p, prune)
+                })
+                .sum(),
+            FlatNode::Player { player, infoset } => {
+                let slots = self.tree.infoset_slots(infoset);
+                let sigma = regret_matching(&self.regrets[slots.clone()]);
+                let children = self.tree.children(node);
+                if player == updated {
+                    // `None` for pruned actions (which have zero probability in `sigma`)
+                    let values: Vec<Option<f64>> = children
+                        .zip(slots.clone())
+                        .zip(&sigma)
+                        .map(|((c, slot), &s)| {
+                            if s == 0.0 && prune.is_some_and(|t| self.regrets[slot] < t) {
+                                None
+                            } else {
+                                Some(self.cfr_rec(updated, c, p_updated * s, p_others, prune))
+                            }
+                        })
+                        .collect();
+                    let v = values
+                        .iter()
+                        .zip(&sigma)
+                        .map(|(v, s)| v.map_or(0.0, |v| v * s))
+                        .sum::<f64>();
+                    for (i, slot) in slots.enumerate() {
+                        if let Some(value) = values[i] {
+                            self.regrets[slot] += p_others * (value - v);
+                        }
+                        self.strategy_sum[slot] += p_updated * sigma[i];
+                    }
+                    v
+                } else {
+                    children
+                        .zip(&sigma)
+                        .map(|(c, &s)| s * self.cfr_rec(updated, c, p_updated, p_others * s, prune))
+                        .sum()
+                }
+            }
+        }
+    }
+
+    /// Outer sampling MCCFR iterations with constant exploration `epsilon`.
+    pub fn compute_sampled_rng<R: Rng>(&mut self, iterations: usize, epsilon: f64, rng: &mut R) {
+        let config = MCCFRConfig {
+            exploration: Exploration::Constant(epsilon),
+            ..MCCFRConfig::default()
+        };
+        self.compute_sampled(iterations, &config, rng);
+    }
+
+    /// Outer sampling MCCFR iterations with the given exploration and chance sampling.
+    pub fn compute_sampled<R: Rng>(
+        &mut self,
+        iterations: usize,
+        config: &MCCFRConfig,
+        rng: &mut R,
+    ) {
+        config.assert_valid();
+        let start = Instant::now();
+        for _i in 0..iterations {
+            let prune = self.pruning.and_then(|p| p.threshold(self.iterations));
+            for player in 0..self.tree.players() {
+                let mut sampler = Sampler {
+                    tree: &self.tree,
+                    tables: &mut FlatTables {
+                        offsets: &self.tree.infoset_offsets,
+                        regrets: &mut self.regrets,
+                        strategy_sum: &mut self.strategy_sum,
+                    },
+                    rng: &mut *rng,
+                    config,
+                    iteration: self.iterations,
+                    updated: player,
+                    prune,
+                    nodes_traversed: 0,
+                };
+                sampler.sample(0, 0, 1.0, 1.0, 1.0);
+                self.nodes_traversed += sampler.nodes_traversed;
+            }
+            self.iterations += 1;
+        }
+        self.elapsed += start.elapsed();
+    }
+}
+
+impl<G: Game> SampledTree for FlatTree<G> {
+    type Node = usize;
+    type InfoSet = usize;
+
+    fn sampled_node<'a>(&'a self, node: &'a usize) -> SampledNode<'a, usize> {
+        match self.node(*node) {
+            FlatNode::Terminal(u) => SampledNode::Terminal(u),
+            FlatNode::Chance => SampledNode::Chance(&self.chance_prob[self.children(*node)]),
+            FlatNode::Player { player, infoset } => {
+                SampledNode::Player(player, infoset, self.children(*node).len())
+            }
+        }
+    }
+
+    fn child(&self, node: &usize, action: usize) -> usize {
+        self.children(*node).start + action
+    }
+}
+
+/// The `FlatCFR` tables as seen by `Sampler`.
+struct FlatTables<'a> {
+    offsets: &'a [u32],
+    regrets: &'a mut [f64],
+    strategy_sum: &'a mut [f64],
+}
+
+impl<'a> FlatTables<'a> {
+    fn slots(&self, infoset: usize) -> Range<usize> {
+        self.offsets[infoset] as usize..self.offsets[infoset + 1] as usize
+    }
+}
+
+impl<'a> RegretTables<usize> for FlatTables<'a> {
+    fn entry(&self, infoset: &usize) -> Option<(&[f64], &[f64])> {
+        let slots = self.slots(*infoset);
+        Some((&self.strategy_sum[slots.clone()], &self.regrets[slots]))
+    }
+
+    fn update(&mut self, infoset: usize, d_reg: Option<&[f64]>, d_strat: Option<&[f64]>) {
+        let slots = self.slots(infoset);
+        if let Some(d) = d_reg {
+            for (r, d) in self.regrets[slots.clone()].iter_mut().zip(d) {
+                *r += d;
+            }
+        }
+        if let Some(d) = d_strat {
+            for (s, d) in self.strategy_sum[slots].iter_mut().zip(d) {
+                *s += d;
+            }
+        }
+    }
+}
+
+/// Plays the average strategy, uniform in information sets missing from the tree.
+impl<G: Game> Strategy<G> for FlatCFR<G> {
+    fn policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        let (p, n) = match active {
+            ActivePlayer::Player(p, ref actions) => (*p as usize, actions.len()),
+            _ => panic!("strategy requested for non-player state {:?}", active),
+        };
+        let vs = (0..n as ActionIndex).collect::<Vec<_>>();
+        match self.tree.infoset_of(p, obs) {
+            Some(i) => Categorical::new(self.average_strategy(i), vs),
+            None => Categorical::uniform(vs),
+        }
+    }
+}
+
+/// Iterations are full-tree CFR iterations (`compute`), the RNG is not used. The sampled
+/// mode (`compute_sampled`) is not available through `Solver`.
+impl<G: Game> Solver<G> for FlatCFR<G> {
+    /// Cumulative regrets and strategy sums of all the information set slots.
+    type Tables = (Vec<f64>, Vec<f64>);
+
+    fn iterate(&mut self, iterations: usize, _rng: &mut dyn RngCore) {
+        self.compute(iterations);
+    }
+
+    fn current_policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        let (p, n) = match active {
+            ActivePlayer::Player(p, ref actions) => (*p as usize, actions.len()),
+            _ => panic!("policy requested for non-player state {:?}", active),
+        };
+        let vs = (0..n as ActionIndex).collect::<Vec<_>>();
+        match self.tree.infoset_of(p, obs) {
+            Some(i) => Categorical::new(self.current_strategy(i), vs),
+            None => Categorical::uniform(vs),
+        }
+    }
+
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            iterations: self.iterations,
+            nodes_traversed: self.nodes_traversed,
+            infosets: self.tree.infosets(),
+            elapsed: self.elapsed,
+        }
+    }
+
+    fn save(&self) -> SolverSnapshot<Self::Tables> {
+        SolverSnapshot {
+            iterations: self.iterations,
+            nodes_traversed: self.nodes_traversed,
+            elapsed: self.elapsed,
+            tables: (self.regrets.clone(), self.strategy_sum.clone()),
+        }
+    }
+
+    fn restore(&mut self, snapshot: SolverSnapshot<Self::Tables>) {
+        let (regrets, strategy_sum) = snapshot.tables;
+        assert_eq!(
+            regrets.len(),
+            self.regrets.len(),
+            "snapshot of a different tree"
+        );
+        assert_eq!(strategy_sum.len(), self.strategy_sum.len());
+        self.iterations = snapshot.iterations;
+        self.nodes_traversed = snapshot.nodes_traversed;
+        self.elapsed = snapshot.elapsed;
+        self.regrets = regrets;
+        self.strategy_sum = strategy_sum;
+    }
+
+    fn table_snapshot(&self) -> TableSnapshot {
+        let mut snapshot = TableSnapshot::new(self.iterations, self.nodes_traversed);
+        for (player, index) in self.tree.infoset_index.iter().enumerate() {
+            for (obs, &infoset) in index {
+                let slots = self.tree.infoset_slots(infoset as usize);
+                let entry = TableEntry {
+                    regrets: self.regrets[slots.clone()].to_vec(),
+                    strategy_sum: self.strategy_sum[slots].to_vec(),
+                };
+                snapshot.insert(player, obs, entry);
+            }
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FlatCFR, FlatNode, FlatTree, Pruning};
+    use crate::{exploitability, goofspiel, ChanceSampling, Exploration, Game, Goofspiel};
+    use crate::{MCCFRConfig, Strategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_flat_goof3() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let t = FlatTree::from_game(&g);
+        assert_eq!(t.node(0), FlatNode::Chance);
+        assert_eq!(t.children(0), 1..4);
+        assert_eq!(t.chance_prob(1), 1.0 / 3.0);
+        assert_eq!(t.infosets(), 426);
+
+        let mut cfr = FlatCFR::new(t.clone());
+        cfr.compute(200);
+        let s = g.play_owned(g.start(), 1);
+        let pol = cfr.policy(&s.active, &s.observations[0]);
+        assert!(pol.probs()[1] > 0.8);
+
+        let mut mc = FlatCFR::new(t.clone());
+        mc.compute_sampled_rng(5000, 0.6, &mut SmallRng::seed_from_u64(1));
+        let s = g.play_owned(s, 1);
+        let pol = mc.policy(&s.active, &s.observations[1]);
+        assert!(pol.probs()[1] > 0.8);
+
+        let config = MCCFRConfig {
+            exploration: Exploration::Decaying {
+                initial: 0.6,
+                rate: 0.01,
+                min: 0.1,
+            },
+            chance: ChanceSampling::Enumerate,
+            ..MCCFRConfig::default()
+        };
+        let mut mc = FlatCFR::new(t);
+        mc.compute_sampled(2000, &config, &mut SmallRng::seed_from_u64(1));
+        let pol = mc.policy(&s.active, &s.observations[1]);
+        assert!(pol.probs()[1] > 0.8);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let t = FlatTree::from_game(&g);
+        let mut full = FlatCFR::new(t.clone());
+        full.compute(200);
+        let mut pruned = FlatCFR::new(t).with_pruning(Pruning::new(-1.0, 10, 20));
+        pruned.compute(200);
+        assert!(pruned.nodes_traversed < full.nodes_traversed * 2 / 3);
+        assert!(exploitability(&g, &pruned) < 0.05);
+        assert!(exploitability(&g, &full) < 0.05);
+    }
+}
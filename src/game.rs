@@ -32,7 +32,7 @@ pub trait Game: Debug + Clone {
             panic!("playing in terminal state {:?}", hist);
         }
         match hist.active.actions().iter().position(|x| x == action) {
-            Some(idx) => self.play(&hist, idx),
+            Some(idx) => self.play(hist, idx),
             None => panic!("action {:?} not available in state {:?}", action, hist),
         }
     }
@@ -51,7 +51,7 @@ pub trait Game: Debug + Clone {
         let action = hist
             .active
             .actions()
-            .get(action_index as usize)
+            .get(action_index)
             .expect("action index outside action list")
             .clone();
         // Game-specific logic and checks
@@ -1,23 +1,77 @@
 use crate::{ActivePlayer, Categorical, Game, HistoryInfo, Utility};
-use bit_set::BitSet;
+
+/// Maximum number of players.
+pub const MAX_PLAYERS: usize = 6;
+/// Maximum number of cards.
+pub const MAX_CARDS: usize = 63;
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
 pub enum Scoring {
+    /// Own score minus the mean score of the opponents (score difference for two players).
     ZeroSum,
+    /// 1 for the players with the highest score, -1 for the others, 0 for everyone when
+    /// all the scores are equal.
     WinLoss,
     Absolute,
 }
 
+/// Order in which the point cards are played.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+pub enum PointOrder {
+    /// Uniformly random, drawn by chance and revealed to all.
+    Random,
+    /// Fixed order from the lowest card, no chance.
+    Ascending,
+    /// Fixed order from the highest card, no chance.
+    Descending,
+}
+
+/// What the players learn at the end of every round.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+pub enum Reveal {
+    /// Only the winner of the round.
+    Winner,
+    /// All the bids.
+    Bids,
+}
+
+/// What happens with the point card when the highest bid is tied.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+pub enum Ties {
+    /// Nobody gets the points.
+    Discard,
+    /// The points are added to the next round's card (lost if tied in the last round).
+    CarryOver,
+}
+
+/// Observations of Goofspiel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Info {
+    /// The point card drawn by chance.
+    Card(u32),
+    /// The winner of the round, `None` on a tie.
+    Winner(Option<u32>),
+    /// The bids of all players in the round.
+    Bids(Vec<u32>),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Goofspiel {
     /// Number of cards.
     pub cards: usize,
     /// Final scoring type.
     pub scoring: Scoring,
-    /// Point values of the chance cards (cards in hands always have strength 0..N-1).
+    /// Point values of the chance cards.
     pub values: Vec<Utility>,
-    /// Cached full-hand bit set
-    card_set: BitSet,
+    /// Bidding strengths of the hand cards (`1..=cards` by default).
+    pub strengths: Vec<u32>,
+    /// Number of rounds played, at most `cards`.
+    pub rounds: usize,
+    /// Number of players.
+    pub players: usize,
+    pub order: PointOrder,
+    pub reveal: Reveal,
+    pub ties: Ties,
 }
 
 impl Goofspiel {
@@ -29,44 +83,157 @@ impl Goofspiel {
         )
     }
 
+    /// A game where the point card `i` is worth `values[i - 1]`.
     pub fn with_values<V: Into<Vec<Utility>>>(cards: usize, scoring: Scoring, values: V) -> Self {
+        let values = values.into();
+        assert!(
+            (1..=MAX_CARDS).contains(&cards),
+            "unsupported number of cards"
+        );
+        assert_eq!(values.len(), cards, "one point value needed per card");
         Goofspiel {
             cards,
             scoring,
-            card_set: (1..cards + 1).collect(),
-            values: values.into(),
+            values,
+            strengths: (1..cards as u32 + 1).collect(),
+            rounds: cards,
+            players: 2,
+            order: PointOrder::Random,
+            reveal: Reveal::Winner,
+            ties: Ties::Discard,
+        }
+    }
+
+    /// End the game after `rounds` rounds, leaving the remaining cards unplayed.
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        assert!(rounds >= 1 && rounds <= self.cards);
+        self.rounds = rounds;
+        self
+    }
+
+    /// Set the strengths of the hand cards; the highest strength wins a round, equal
+    /// strengths are a tie. Actions and revealed bids are still the card numbers.
+    pub fn with_strengths<V: Into<Vec<u32>>>(mut self, strengths: V) -> Self {
+        let strengths = strengths.into();
+        assert_eq!(strengths.len(), self.cards);
+        self.strengths = strengths;
+        self
+    }
+
+    pub fn with_players(mut self, players: usize) -> Self {
+        assert!(
+            (2..=MAX_PLAYERS).contains(&players),
+            "unsupported number of players"
+        );
+        self.players = players;
+        self
+    }
+
+    pub fn with_order(mut self, order: PointOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_reveal(mut self, reveal: Reveal) -> Self {
+        self.reveal = reveal;
+        self
+    }
+
+    pub fn with_ties(mut self, ties: Ties) -> Self {
+        self.ties = ties;
+        self
+    }
+
+    /// The point card (if fixed) and active player at the start of round `round`.
+    fn round_start(&self, round: usize, cards: &[u64]) -> (Option<u32>, ActivePlayer<Self>) {
+        let card = match self.order {
+            PointOrder::Random => {
+                let acts = hand_cards(cards[self.players]);
+                return (None, ActivePlayer::Chance(Categorical::uniform(acts)));
+            }
+            PointOrder::Ascending => round + 1,
+            PointOrder::Descending => self.cards - round,
+        } as u32;
+        (Some(card), self.bidder(0, cards[0]))
+    }
+
+    fn bidder(&self, player: usize, hand: u64) -> ActivePlayer<Self> {
+        ActivePlayer::Player(player as u32, hand_cards(hand))
+    }
+
+    fn payoffs(&self, scores: &[f64]) -> Vec<Utility> {
+        let n = self.players as f64;
+        let total: f64 = scores.iter().sum();
+        let zero_sum = scores.iter().map(|s| s - (total - s) / (n - 1.0));
+        match self.scoring {
+            Scoring::Absolute => scores.to_vec(),
+            Scoring::ZeroSum => zero_sum.collect(),
+            Scoring::WinLoss => {
+                let best = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                if scores.iter().all(|&s| s == best) {
+                    vec![0.0; scores.len()]
+                } else {
+                    scores
+                        .iter()
+                        .map(|&s| if s == best { 1.0 } else { -1.0 })
+                        .collect()
+                }
+            }
         }
     }
 }
 
-/// Players are p0 and p1, p2 is chance
-#[derive(Clone, Debug)]
+/// The cards of a hand bit mask (bit `c` for card `c`), in increasing order.
+fn hand_cards(mut hand: u64) -> Vec<u32> {
+    let mut res = Vec::with_capacity(hand.count_ones() as usize);
+    while hand != 0 {
+        res.push(hand.trailing_zeros());
+        hand &= hand - 1;
+    }
+    res
+}
+
+/// A fixed-size state, copied without allocation in every step. Only the first `players`
+/// entries of the arrays are used.
+#[derive(Clone, Copy, Debug)]
 pub struct State {
-    cards: [BitSet; 3],
-    scores: [f64; 2],
+    /// Hands of the players followed by the remaining point cards, as bit masks.
+    cards: [u64; MAX_PLAYERS + 1],
+    scores: [f64; MAX_PLAYERS],
+    round: usize,
+    /// The point card of the current round (once drawn).
+    card: Option<u32>,
+    /// Bids of the current round.
+    bids: [u32; MAX_PLAYERS],
+    /// Number of bids in the current round.
+    bid_count: usize,
+    /// Points carried over from tied rounds.
+    pot: Utility,
 }
 
 impl Game for Goofspiel {
     type State = State;
-    type Observation = i32;
+    type Observation = Info;
     type Action = u32;
 
     fn players(&self) -> usize {
-        2
+        self.players
     }
 
     fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let full = ((1u64 << self.cards) - 1) << 1;
+        let mut cards = [0; MAX_PLAYERS + 1];
+        cards[..=self.players].iter_mut().for_each(|h| *h = full);
+        let (card, active) = self.round_start(0, &cards);
         let state = State {
-            cards: [
-                self.card_set.clone(),
-                self.card_set.clone(),
-                self.card_set.clone(),
-            ],
-            scores: [0.0, 0.0],
+            cards,
+            scores: [0.0; MAX_PLAYERS],
+            round: 0,
+            card,
+            bids: [0; MAX_PLAYERS],
+            bid_count: 0,
+            pot: 0.0,
         };
-        let active = ActivePlayer::Chance(Categorical::uniform(
-            self.card_set.iter().map(|x| x as u32).collect::<Vec<_>>(),
-        ));
         (state, active)
     }
 
@@ -79,55 +246,67 @@ impl Game for Goofspiel {
         ActivePlayer<Self>,
         Vec<Option<Self::Observation>>,
     ) {
-        let history = &hist.history;
-        // p=0, p=1 are players p=2 is chance
-        let len = history.len();
-        let prev_player = (history.len() + 2) % 3;
-        let next_player = (history.len()) % 3;
+        let n = self.players;
+        let mut state = hist.state;
         let mut obs = None;
-        // Play the selected card, update state
-        let mut state = hist.state.clone();
-        state.cards[prev_player].remove(*action as usize);
-        // Score update and Obs
-        if prev_player == 1 {
-            let bet = self.values[(history[len - 2] - 1) as usize];
-            let winner = ((history[len - 1] as i32) - (*action as i32)).signum();
-            if winner == 1 {
-                state.scores[0] += bet;
-            }
-            if winner == -1 {
-                state.scores[1] += bet;
-            }
-            obs = Some(winner);
-        }
-        // Observe public card
-        if prev_player == 2 {
-            obs = Some(*action as i32);
+        let prev_player = hist.active.player().unwrap_or(n);
+        state.cards[prev_player] &= !(1 << *action);
+        if prev_player == n {
+            // Observe public card
+            state.card = Some(*action);
+            obs = Some(Info::Card(*action));
+        } else {
+            state.bids[state.bid_count] = *action;
+            state.bid_count += 1;
         }
-        // Terminal reached or active player
-        let active = if len + 1 == self.cards * 3 {
-            let d = state.scores[0] - state.scores[1];
-            ActivePlayer::Terminal(match self.scoring {
-                Scoring::Absolute => state.scores.as_ref().into(),
-                Scoring::ZeroSum => vec![d, -d],
-                Scoring::WinLoss => vec![d.signum(), -d.signum()],
-            })
+        let active = if state.bid_count < n {
+            let next = state.bid_count;
+            self.bidder(next, state.cards[next])
         } else {
-            let acts = state.cards[next_player].iter().map(|x| x as u32).collect();
-            if next_player == 2 {
-                ActivePlayer::Chance(Categorical::uniform(acts))
+            // Score update and Obs
+            let card = state.card.take().expect("round without a point card") as usize;
+            state.cards[n] &= !(1 << card);
+            let bet = self.values[card - 1] + state.pot;
+            let bids = &state.bids[..n];
+            let strength = |b: u32| self.strengths[b as usize - 1];
+            let max = bids.iter().map(|&b| strength(b)).max().unwrap();
+            let winner = if bids.iter().filter(|&&b| strength(b) == max).count() == 1 {
+                bids.iter().position(|&b| strength(b) == max)
             } else {
-                ActivePlayer::Player(next_player as u32, acts)
+                None
+            };
+            match winner {
+                Some(w) => {
+                    state.scores[w] += bet;
+                    state.pot = 0.0;
+                }
+                None if self.ties == Ties::CarryOver => state.pot = bet,
+                None => (),
+            }
+            obs = Some(match self.reveal {
+                Reveal::Winner => Info::Winner(winner.map(|w| w as u32)),
+                Reveal::Bids => Info::Bids(state.bids[..n].to_vec()),
+            });
+            state.bid_count = 0;
+            state.round += 1;
+            // Terminal reached or next round
+            if state.round == self.rounds {
+                ActivePlayer::Terminal(self.payoffs(&state.scores[..n]))
+            } else {
+                let (card, active) = self.round_start(state.round, &state.cards);
+                state.card = card;
+                active
             }
         };
-        // Return new info triplet
-        (state, active, vec![obs; 3])
+        // Return new info
+        (state, active, vec![obs; n + 1])
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::{ActivePlayer, Categorical, Game, Goofspiel, Scoring};
+    use super::{Info::*, PointOrder, Reveal, Ties};
     use crate::Observation::*;
 
     #[test]
@@ -150,50 +329,127 @@ mod test {
             assert_eq!(
                 hist.observations[0],
                 vec![
-                    Obs(2),
+                    Obs(Card(2)),
                     Own(1),
-                    Obs(-1),
-                    Obs(3),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(3)),
                     Own(2),
-                    Obs(-1),
-                    Obs(4),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(4)),
                     Own(3),
-                    Obs(0),
-                    Obs(1),
+                    Obs(Winner(None)),
+                    Obs(Card(1)),
                     Own(4),
-                    Obs(1)
+                    Obs(Winner(Some(0)))
                 ]
             );
             assert_eq!(
                 hist.observations[1],
                 vec![
-                    Obs(2),
+                    Obs(Card(2)),
                     Own(2),
-                    Obs(-1),
-                    Obs(3),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(3)),
                     Own(4),
-                    Obs(-1),
-                    Obs(4),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(4)),
                     Own(3),
-                    Obs(0),
-                    Obs(1),
+                    Obs(Winner(None)),
+                    Obs(Card(1)),
                     Own(1),
-                    Obs(1)
+                    Obs(Winner(Some(0)))
                 ]
             );
             assert_eq!(
                 hist.observations[2],
                 vec![
-                    Obs(2),
-                    Obs(-1),
-                    Obs(3),
-                    Obs(-1),
-                    Obs(4),
-                    Obs(0),
-                    Obs(1),
-                    Obs(1)
+                    Obs(Card(2)),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(3)),
+                    Obs(Winner(Some(1))),
+                    Obs(Card(4)),
+                    Obs(Winner(None)),
+                    Obs(Card(1)),
+                    Obs(Winner(Some(0)))
                 ]
             );
         }
     }
+
+    #[test]
+    fn test_variants() {
+        // Three players, descending points, bids revealed, ties carried over
+        let g = Goofspiel::new(3, Scoring::Absolute)
+            .with_players(3)
+            .with_order(PointOrder::Descending)
+            .with_reveal(Reveal::Bids)
+            .with_ties(Ties::CarryOver);
+        let mut hist = g.start();
+        assert_eq!(hist.active, ActivePlayer::Player(0, vec![1, 2, 3]));
+        for a in &[3, 3, 1] {
+            hist = g.play_value(&hist, a);
+        }
+        assert_eq!(hist.active, ActivePlayer::Player(0, vec![1, 2]));
+        for a in &[1, 2, 3, 2, 1, 2] {
+            hist = g.play_value(&hist, a);
+        }
+        // Card 3 tied, card 2 (+3) to player 2, card 1 tied in the last round
+        assert_eq!(hist.active, ActivePlayer::Terminal(vec![0.0, 0.0, 5.0]));
+        assert_eq!(
+            hist.observations[1],
+            vec![
+                Own(3),
+                Obs(Bids(vec![3, 3, 1])),
+                Own(2),
+                Obs(Bids(vec![1, 2, 3])),
+                Own(1),
+                Obs(Bids(vec![2, 1, 2]))
+            ]
+        );
+        let g = g.with_ties(Ties::Discard).with_reveal(Reveal::Winner);
+        let mut hist = g.start();
+        for a in &[3, 3, 1, 1, 2, 3, 2, 1, 2] {
+            hist = g.play_value(&hist, a);
+        }
+        assert_eq!(hist.active, ActivePlayer::Terminal(vec![0.0, 0.0, 2.0]));
+        assert_eq!(hist.observations[3][0], Obs(Winner(None)));
+
+        // Win-loss scoring ranks the players, ties are not wins
+        let g = Goofspiel::new(3, Scoring::WinLoss).with_players(3);
+        assert_eq!(g.payoffs(&[1.0, 5.0, 0.0]), vec![-1.0, 1.0, -1.0]);
+        assert_eq!(g.payoffs(&[4.0, 4.0, 0.0]), vec![1.0, 1.0, -1.0]);
+        assert_eq!(g.payoffs(&[2.0, 2.0, 2.0]), vec![0.0; 3]);
+        let g = Goofspiel::new(3, Scoring::WinLoss);
+        assert_eq!(g.payoffs(&[3.0, 3.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rounds_and_strengths() {
+        // Card 1 beats everything, two rounds out of four
+        let g = Goofspiel::new(4, Scoring::Absolute)
+            .with_rounds(2)
+            .with_strengths(vec![5, 2, 3, 4])
+            .with_order(PointOrder::Ascending);
+        let mut hist = g.start();
+        for a in &[4, 1, 1, 2] {
+            hist = g.play_value(&hist, a);
+        }
+        assert_eq!(hist.active, ActivePlayer::Terminal(vec![2.0, 1.0]));
+        assert_eq!(
+            hist.observations[2],
+            vec![Obs(Winner(Some(1))), Obs(Winner(Some(0)))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "one point value needed per card")]
+    fn test_short_values() {
+        Goofspiel::with_values(4, Scoring::ZeroSum, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported number of players")]
+    fn test_too_many_players() {
+        Goofspiel::new(3, Scoring::ZeroSum).with_players(super::MAX_PLAYERS + 1);
+    }
 }
@@ -23,7 +23,7 @@ pub enum ActivePlayer<G: Game> {
 
 impl<G: Game> ActivePlayer<G> {
     #[inline]
-    pub fn actions<'a>(&'a self) -> &'a [G::Action] {
+    pub fn actions(&self) -> &[G::Action] {
         match self {
             ActivePlayer::Terminal(_) => &[],
             ActivePlayer::Player(_, ref actions) => actions,
@@ -32,7 +32,7 @@ impl<G: Game> ActivePlayer<G> {
     }
 
     #[inline]
-    pub fn player<'a>(&'a self) -> Option<usize> {
+    pub fn player(&self) -> Option<usize> {
         match self {
             ActivePlayer::Terminal(_) => None,
             ActivePlayer::Player(p, _) => Some(*p as usize),
@@ -55,7 +55,7 @@ impl<G: Game> HistoryInfo<G> {
         HistoryInfo {
             history_indices: Vec::new(),
             history: Vec::new(),
-            observations: vec![vec! {}; (game.players() + 1) as usize],
+            observations: vec![vec! {}; game.players() + 1],
             state,
             active,
         }
@@ -68,4 +68,12 @@ impl<G: Game> HistoryInfo<G> {
             .map(|(so, oo)| &so[oo.len()..])
             .collect()
     }
+
+    /// Observations of the public observer (the last slot of `observations`),
+    /// identifying the public state of this history.
+    pub fn public_state_key(&self) -> &[PlayerObservation<G>] {
+        self.observations
+            .last()
+            .expect("history without the public observation slot")
+    }
 }
@@ -0,0 +1,335 @@
+use crate::{ActionIndex, ActivePlayer, Game, HistoryInfo, Observation};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// A game tree cache built lazily while playing.
+///
+/// Wraps `game` with the same actions and observations; every node is computed by the
+/// wrapped game on its first visit and cached in the tree, so repeated visits (e.g. by
+/// sampling solvers) skip `update_state`. At most `max_nodes` nodes are cached, nodes visited
+/// beyond that are computed every time. Clones of the game share the cache.
+///
+/// Cached children are looked up by action index without locking. Note that the wrapped game's
+/// `play_owned` is not used, only its `update_state`.
+#[derive(Clone, Debug)]
+pub struct LazyTreeGame<G: Game> {
+    pub game: G,
+    root: Arc<LazyNode<G>>,
+    cached: Arc<AtomicUsize>,
+    max_nodes: usize,
+}
+
+/// A node of a `LazyTreeGame` with the wrapped game's state.
+pub struct LazyNode<G: Game> {
+    state: G::State,
+    active: ActivePlayer<G>,
+    /// Observations emitted when entering the node.
+    observations: Vec<Option<G::Observation>>,
+    /// Cached children by action index, the slots are allocated on the first expansion.
+    children: OnceLock<Children<G>>,
+}
+
+/// One once-initialised slot per action.
+type Children<G> = Box<[OnceLock<Arc<LazyNode<G>>>]>;
+
+impl<G: Game> fmt::Debug for LazyNode<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Children are left out, they may be the whole tree
+        f.debug_struct("LazyNode")
+            .field("state", &self.state)
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+impl<G: Game> LazyNode<G> {
+    fn new(
+        state: G::State,
+        active: ActivePlayer<G>,
+        observations: Vec<Option<G::Observation>>,
+    ) -> Self {
+        LazyNode {
+            state,
+            active,
+            observations,
+            children: OnceLock::new(),
+        }
+    }
+
+    /// State of the wrapped game.
+    pub fn state(&self) -> &G::State {
+        &self.state
+    }
+
+    /// The cached child after the action with the given index.
+    fn child(&self, idx: usize) -> Option<&Arc<LazyNode<G>>> {
+        self.children.get().and_then(|c| c[idx].get())
+    }
+}
+
+impl<G: Game> LazyTreeGame<G> {
+    /// Wrap `game` without a limit on the cached nodes.
+    pub fn new(game: G) -> Self {
+        Self::with_max_nodes(game, usize::MAX)
+    }
+
+    pub fn with_max_nodes(game: G, max_nodes: usize) -> Self {
+        let (state, active) = game.initial_state();
+        let observations = vec![None; game.players() + 1];
+        LazyTreeGame {
+            game,
+            root: Arc::new(LazyNode::new(state, active, observations)),
+            cached: Arc::new(AtomicUsize::new(1)),
+            max_nodes,
+        }
+    }
+
+    /// Number of cached nodes including the root.
+    pub fn cached_nodes(&self) -> usize {
+        self.cached.load(Ordering::Relaxed)
+    }
+
+    /// Expand the tree breadth-first until `nodes` nodes are cached (or the cap or the whole
+    /// tree is reached). Iterative, so also usable for deep games. Returns the cached node count.
+    pub fn expand(&self, nodes: usize) -> usize {
+        let limit = nodes.min(self.max_nodes);
+        let mut queue = VecDeque::new();
+        queue.push_back((self.root.clone(), Vec::new()));
+        while let Some((node, path)) = queue.pop_front() {
+            // The history is only rebuilt for nodes with uncached children
+            let mut hist = None;
+            for i in 0..node.active.actions().len() {
+                if self.cached_nodes() >= limit {
+                    return self.cached_nodes();
+                }
+                let child = match node.child(i) {
+                    Some(child) => child.clone(),
+                    None => {
+                        let h = hist.get_or_insert_with(|| self.wrapped_history(&path));
+                        self.compute_child(&node, h, i)
+                    }
+                };
+                let mut child_path = path.clone();
+                child_path.push(i as ActionIndex);
+                queue.push_back((child, child_path));
+            }
+        }
+        self.cached_nodes()
+    }
+
+    /// The wrapped game's history after the cached action indices `path`.
+    fn wrapped_history(&self, path: &[ActionIndex]) -> HistoryInfo<G> {
+        let hist = path
+            .iter()
+            .fold(self.start(), |h, &ai| self.play_owned(h, ai as usize));
+        HistoryInfo {
+            state: hist.state.state.clone(),
+            active: hist.state.active.clone(),
+            history_indices: hist.history_indices,
+            history: hist.history,
+            observations: hist.observations,
+        }
+    }
+
+    /// Compute the child of `node` (with the wrapped history `src`) after the action with
+    /// index `idx` and cache it if the cap allows.
+    fn compute_child(
+        &self,
+        node: &LazyNode<G>,
+        src: &HistoryInfo<G>,
+        idx: usize,
+    ) -> Arc<LazyNode<G>> {
+        let (state, active, obs) = self.game.update_state(src, &node.active.actions()[idx]);
+        let child = Arc::new(LazyNode::new(state, active, obs));
+        // Reserve a slot in the cache, released if another thread cached the child meanwhile
+        let max_nodes = self.max_nodes;
+        let reserved = self
+            .cached
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                if n < max_nodes {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok();
+        if !reserved {
+            return child;
+        }
+        let slots = node.children.get_or_init(|| {
+            (0..node.active.actions().len())
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        match slots[idx].set(child.clone()) {
+            Ok(()) => child,
+            Err(_) => {
+                self.cached.fetch_sub(1, Ordering::Relaxed);
+                slots[idx].get().expect("set").clone()
+            }
+        }
+    }
+
+    fn convert(active: &ActivePlayer<G>) -> ActivePlayer<Self> {
+        match active {
+            ActivePlayer::Player(p, ref a) => ActivePlayer::Player(*p, a.clone()),
+            ActivePlayer::Chance(ref d) => ActivePlayer::Chance(d.clone()),
+            ActivePlayer::Terminal(ref u) => ActivePlayer::Terminal(u.clone()),
+        }
+    }
+}
+
+impl<G: Game> Game for LazyTreeGame<G> {
+    type State = Arc<LazyNode<G>>;
+    type Observation = G::Observation;
+    type Action = G::Action;
+
+    fn players(&self) -> usize {
+        self.game.players()
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        (self.root.clone(), Self::convert(&self.root.active))
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let node = &hist.state;
+        let idx = node
+            .active
+            .actions()
+            .iter()
+            .position(|a| a == action)
+            .expect("action not available");
+        let child = match node.child(idx) {
+            Some(child) => child.clone(),
+            None => {
+                let src = HistoryInfo {
+                    history_indices: hist.history_indices.clone(),
+                    history: hist.history.clone(),
+                    active: node.active.clone(),
+                    observations: hist.observations.clone(),
+                    state: node.state.clone(),
+                };
+                self.compute_child(node, &src, idx)
+            }
+        };
+        let (active, obs) = (Self::convert(&child.active), child.observations.clone());
+        (child, active, obs)
+    }
+
+    /// As the default, but looks up the cached child by the index and moves the history
+    /// into the wrapped game instead of copying it.
+    fn play_owned(&self, hist: HistoryInfo<Self>, action_index: usize) -> HistoryInfo<Self> {
+        if let ActivePlayer::Terminal(_) = hist.active {
+            panic!("playing in terminal state {:?}", hist);
+        }
+        let HistoryInfo {
+            state: node,
+            active,
+            mut history_indices,
+            mut history,
+            mut observations,
+        } = hist;
+        let action = active
+            .actions()
+            .get(action_index)
+            .expect("action index outside action list")
+            .clone();
+        let child = match node.child(action_index) {
+            Some(child) => child.clone(),
+            None => {
+                let src = HistoryInfo {
+                    history_indices,
+                    history,
+                    observations,
+                    active: node.active.clone(),
+                    state: node.state.clone(),
+                };
+                let child = self.compute_child(&node, &src, action_index);
+                history_indices = src.history_indices;
+                history = src.history;
+                observations = src.observations;
+                child
+            }
+        };
+        if let Some(p) = active.player() {
+            observations[p].push(Observation::Own(action.clone()));
+        }
+        history_indices.push(action_index as ActionIndex);
+        history.push(action);
+        for (ovec, ob) in observations.iter_mut().zip(&child.observations) {
+            if let Some(ob) = ob {
+                ovec.push(Observation::Obs(ob.clone()))
+            }
+        }
+        HistoryInfo {
+            active: Self::convert(&child.active),
+            state: child,
+            history,
+            history_indices,
+            observations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LazyTreeGame;
+    use crate::{goofspiel, Game, Goofspiel, OuterMCCFR, Strategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_lazy_goofspiel() {
+        let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+        let t = LazyTreeGame::with_max_nodes(g.clone(), 100);
+        let (mut h, mut h2) = (g.start(), t.start());
+        for a in &[2, 1, 2, 3, 2, 4, 4, 3, 3, 1, 4, 1] {
+            h = g.play_value(&h, a);
+            h2 = t.play_value(&h2, a);
+        }
+        assert_eq!(h.observations, h2.observations);
+        assert_eq!(h.active.actions(), h2.active.actions());
+        assert_eq!(t.cached_nodes(), 13);
+        // Replaying does not grow the cache
+        let mut h3 = t.start();
+        for i in &h2.history_indices {
+            h3 = t.play(&h3, *i as usize);
+        }
+        assert_eq!(t.cached_nodes(), 13);
+        assert_eq!(t.expand(1000), 100);
+
+        let mut mc = OuterMCCFR::new(t.clone());
+        mc.compute_rng(100, 0.6, &mut SmallRng::seed_from_u64(1));
+        assert_eq!(t.cached_nodes(), 100);
+        let s = t.start();
+        let s = t.play_owned(s, 1);
+        assert_eq!(mc.policy(&s.active, &s.observations[0]).items().len(), 4);
+        assert_eq!(
+            LazyTreeGame::new(Goofspiel::new(2, goofspiel::Scoring::ZeroSum)).expand(1000),
+            39
+        );
+    }
+
+    #[test]
+    fn test_lazy_threads() {
+        let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+        let t = LazyTreeGame::with_max_nodes(g, 500);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| t.expand(1000));
+            }
+        });
+        assert_eq!(t.cached_nodes(), 500);
+        assert_eq!(t.expand(1000), 500);
+    }
+}
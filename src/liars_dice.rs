@@ -0,0 +1,258 @@
+use crate::{ActivePlayer, Categorical, Game, HistoryInfo};
+
+/// Liar's Dice without wild faces.
+///
+/// Every player privately rolls their dice (one chance node per player, the outcome
+/// is the sorted roll). Players then take turns in raising the bid "at least `quantity`
+/// dice in total show `face`" or calling the last bid a lie. On a call, the bidder wins
+/// if the bid holds and the caller wins otherwise. The winner gets 1, the loser -1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiarsDice {
+    /// Number of dice of each player.
+    pub dice: Vec<usize>,
+    /// Number of die faces.
+    pub faces: u32,
+}
+
+/// Actions are also the observations: bids and calls are public, rolls are private.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Sorted roll of the current player's dice.
+    Roll(Vec<u32>),
+    Bid {
+        quantity: u32,
+        face: u32,
+    },
+    Liar,
+}
+
+impl LiarsDice {
+    pub fn new<V: Into<Vec<usize>>>(dice: V, faces: u32) -> Self {
+        let dice = dice.into();
+        assert!(dice.len() >= 2, "at least two players needed");
+        assert!(faces >= 1);
+        assert!(
+            dice.iter().sum::<usize>() > 0,
+            "at least one die needed in total"
+        );
+        LiarsDice { dice, faces }
+    }
+
+    fn total_dice(&self) -> u32 {
+        self.dice.iter().sum::<usize>() as u32
+    }
+
+    /// Bids are ordered by quantity first, face second.
+    fn bid_index(&self, quantity: u32, face: u32) -> u32 {
+        (quantity - 1) * self.faces + (face - 1)
+    }
+
+    /// All bids above `last` (all bids if `None`), followed by `Liar` if there was a bid.
+    fn bid_actions(&self, last: Option<u32>) -> Vec<Action> {
+        let first = last.map(|b| b + 1).unwrap_or(0);
+        let mut acts: Vec<Action> = (first..self.total_dice() * self.faces)
+            .map(|b| Action::Bid {
+                quantity: b / self.faces + 1,
+                face: b % self.faces + 1,
+            })
+            .collect();
+        if last.is_some() {
+            acts.push(Action::Liar);
+        }
+        acts
+    }
+
+    /// Distribution of sorted rolls of `n` dice.
+    fn roll_distribution(&self, n: usize) -> Categorical<Action> {
+        let mut rolls = Vec::new();
+        let mut probs = Vec::new();
+        let mut cur = Vec::with_capacity(n);
+        self.rolls_rec(n, 1, &mut cur, &mut rolls, &mut probs);
+        Categorical::new(probs, rolls)
+    }
+
+    fn rolls_rec(
+        &self,
+        n: usize,
+        min_face: u32,
+        cur: &mut Vec<u32>,
+        rolls: &mut Vec<Action>,
+        probs: &mut Vec<f64>,
+    ) {
+        if cur.len() == n {
+            // Multinomial coefficient n! / prod(count!) over faces^n
+            let mut p = (1..=n).map(|x| x as f64).product::<f64>();
+            let mut i = 0;
+            while i < n {
+                let j = cur[i..].iter().take_while(|&&f| f == cur[i]).count();
+                p /= (1..=j).map(|x| x as f64).product::<f64>();
+                i += j;
+            }
+            probs.push(p / (self.faces as f64).powi(n as i32));
+            rolls.push(Action::Roll(cur.clone()));
+            return;
+        }
+        for f in min_face..=self.faces {
+            cur.push(f);
+            self.rolls_rec(n, f, cur, rolls, probs);
+            cur.pop();
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct State {
+    rolls: Vec<Vec<u32>>,
+    /// Index of the last bid and its bidder.
+    last_bid: Option<(u32, usize)>,
+}
+
+impl Game for LiarsDice {
+    type State = State;
+    type Observation = Action;
+    type Action = Action;
+
+    fn players(&self) -> usize {
+        self.dice.len()
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let state = State {
+            rolls: Vec::new(),
+            last_bid: None,
+        };
+        (
+            state,
+            ActivePlayer::Chance(self.roll_distribution(self.dice[0])),
+        )
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let n = self.players();
+        let mut state = hist.state.clone();
+        let mut obs = vec![None; n + 1];
+        let active = match action {
+            Action::Roll(r) => {
+                // Only the roller observes the roll
+                let p = state.rolls.len();
+                obs[p] = Some(action.clone());
+                state.rolls.push(r.clone());
+                if state.rolls.len() < n {
+                    ActivePlayer::Chance(self.roll_distribution(self.dice[state.rolls.len()]))
+                } else {
+                    ActivePlayer::Player(0, self.bid_actions(None))
+                }
+            }
+            Action::Bid { quantity, face } => {
+                let bidder = hist.active.player().expect("bid by non-player");
+                for (p, o) in obs.iter_mut().enumerate() {
+                    if p != bidder {
+                        *o = Some(action.clone());
+                    }
+                }
+                let b = self.bid_index(*quantity, *face);
+                state.last_bid = Some((b, bidder));
+                let next = (bidder + 1) % n;
+                ActivePlayer::Player(next as u32, self.bid_actions(Some(b)))
+            }
+            Action::Liar => {
+                let caller = hist.active.player().expect("call by non-player");
+                for (p, o) in obs.iter_mut().enumerate() {
+                    if p != caller {
+                        *o = Some(action.clone());
+                    }
+                }
+                let (b, bidder) = state.last_bid.expect("call without a bid");
+                let (quantity, face) = (b / self.faces + 1, b % self.faces + 1);
+                let count = state
+                    .rolls
+                    .iter()
+                    .flat_map(|r| r.iter())
+                    .filter(|&&f| f == face)
+                    .count() as u32;
+                let (winner, loser) = if count >= quantity {
+                    (bidder, caller)
+                } else {
+                    (caller, bidder)
+                };
+                let mut u = vec![0.0; n];
+                u[winner] = 1.0;
+                u[loser] = -1.0;
+                ActivePlayer::Terminal(u)
+            }
+        };
+        (state, active, obs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Action::*, LiarsDice};
+    use crate::Observation::*;
+    use crate::{check_perfect_recall, ActivePlayer, Game, OuterMCCFR};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_liars_dice() {
+        let g = LiarsDice::new(vec![2, 1], 6);
+        let mut h = g.start();
+        if let ActivePlayer::Chance(ref d) = h.active {
+            assert_eq!(d.items().len(), 21);
+            assert!((d.probs().iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!((d.probs()[0] - 1.0 / 36.0).abs() < 1e-9);
+        } else {
+            panic!("expected a chance node");
+        }
+        for a in &[
+            Roll(vec![3, 5]),
+            Roll(vec![5]),
+            Bid {
+                quantity: 1,
+                face: 3,
+            },
+            Bid {
+                quantity: 2,
+                face: 5,
+            },
+            Liar,
+        ] {
+            h = g.play_value(&h, a);
+        }
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![-1.0, 1.0]));
+        assert_eq!(
+            h.observations[0],
+            vec![
+                Obs(Roll(vec![3, 5])),
+                Own(Bid {
+                    quantity: 1,
+                    face: 3
+                }),
+                Obs(Bid {
+                    quantity: 2,
+                    face: 5
+                }),
+                Own(Liar)
+            ]
+        );
+        assert_eq!(h.observations[2].len(), 3);
+
+        let small = LiarsDice::new(vec![1, 1], 3);
+        assert!(check_perfect_recall(&small).is_ok());
+        let mut mc = OuterMCCFR::new(small);
+        mc.compute_rng(100, 0.6, &mut SmallRng::seed_from_u64(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one die")]
+    fn test_no_dice() {
+        LiarsDice::new([0, 0], 6);
+    }
+}
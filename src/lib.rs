@@ -1,22 +1,79 @@
-extern crate bit_set;
+//! Game theory framework: games with imperfect information, strategies and solvers.
+//!
+//! # Reproducibility
+//!
+//! The library never uses an implicit source of randomness: every function that samples
+//! (chance outcomes, strategies, solvers, simulation, arena, subgame re-solving) takes an
+//! explicit `rand::Rng`. With a seeded RNG (e.g. `SmallRng::seed_from_u64(seed)`) and the same
+//! sequence of calls, all results are reproducible bit-for-bit across runs of the same build.
+//! Internal hash maps use a fixed hasher, so no iteration order depends on the process either.
+
 extern crate rand;
 extern crate hashbrown;
 
+mod arena;
+pub mod blotto;
+mod budget;
 mod distribution;
+mod exploitability;
+mod flattree;
 mod game;
 pub mod goofspiel;
 mod history;
+mod lazytree;
+pub mod liars_dice;
 mod mccfr;
+mod observer;
+pub mod oshi_zumo;
+mod public;
+mod recall;
+mod record;
+mod repeated;
+mod simulate;
+mod simultaneous;
+mod solver;
 mod strategy;
+mod subgame;
 mod treegame;
 
+pub use self::arena::{Arena, MatchStats, TournamentResult};
+pub use self::blotto::Blotto;
+pub use self::budget::Budget;
 pub use self::distribution::Categorical;
+pub use self::exploitability::{best_response_value, exploitability};
+pub use self::flattree::{FlatCFR, FlatNode, FlatTree, Pruning};
 pub use self::game::Game;
 pub use self::goofspiel::Goofspiel;
 pub use self::history::{ActivePlayer, HistoryInfo, Observation, PlayerObservation};
-pub use self::mccfr::{OuterMCCFR, RegretStrategy};
+pub use self::lazytree::{LazyNode, LazyTreeGame};
+pub use self::liars_dice::LiarsDice;
+pub use self::mccfr::{
+    ChanceSampling, Exploration, ExplorationPolicy, MCCFRConfig, OuterMCCFR, RegretStrategy,
+};
+pub use self::observer::{CsvLogger, SolverObserver, SolverStats};
+pub use self::oshi_zumo::OshiZumo;
+pub use self::public::{public_state, public_states, PublicState, PublicStateKey};
+pub use self::recall::{
+    check_perfect_recall, check_same_infosets, PartitionMismatch, RecallViolation,
+};
+pub use self::record::{read_records, RecordError, TrajectoryRecord, TrajectoryWriter};
+pub use self::repeated::{
+    Aggregation, Monitoring, Phase, RepeatedGame, RoundAction, RoundObservation, RoundState, Stay,
+    StochasticGame, Transition,
+};
+pub use self::simulate::{mean_utility, playout, simulate, simulate_many, Trajectory};
+pub use self::simultaneous::{
+    MatrixGame, Sequentialized, SequentializedState, SimultaneousGame, Stage,
+};
+pub use self::solver::{Solver, SolverSnapshot, TableEntry, TableSnapshot};
 pub use self::strategy::{Strategy, UniformStrategy};
-pub use self::treegame::TreeGame;
+pub use self::subgame::{
+    resolve_gadget, resolve_subgame, GadgetAction, GadgetObservation, GadgetState,
+    ResolvedStrategy, SubgameGadget,
+};
+pub use self::treegame::{
+    NodeId, TreeBuildError, TreeGame, TreeGameBuilder, TreeGameNode, TreeStrategy,
+};
 
 pub type ActionIndex = u32;
 pub type Utility = f64;
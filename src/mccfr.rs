@@ -1,15 +1,132 @@
 use crate::{
-    ActionIndex, ActivePlayer, Categorical, Game, HistoryInfo, PlayerObservation, Strategy,
+    ActionIndex, ActivePlayer, Categorical, Game, HistoryInfo, PlayerObservation, Pruning, Solver,
+    SolverSnapshot, SolverStats, Strategy, TableEntry, TableSnapshot, Utility,
 };
-use rand::Rng;
 use hashbrown::HashMap;
+use rand::{Rng, RngCore};
+use std::time::{Duration, Instant};
+
+/// How `OuterMCCFR` treats chance nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChanceSampling {
+    /// Follow a single sampled outcome.
+    One,
+    /// Traverse all outcomes, weighted by their probabilities.
+    Enumerate,
+    /// Traverse `k` outcomes chosen uniformly without replacement, weighted by their
+    /// probabilities times `n / k` (for `n` outcomes).
+    WithoutReplacement(usize),
+}
+
+/// Exploration probability of the updated player in `OuterMCCFR`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exploration {
+    Constant(f64),
+    /// `max(min, initial / (1 + rate * iteration))`.
+    Decaying {
+        initial: f64,
+        rate: f64,
+        min: f64,
+    },
+    /// `initial * factor^depth` with the depth being the history length.
+    PerDepth {
+        initial: f64,
+        factor: f64,
+    },
+}
+
+impl Exploration {
+    /// The exploration probability, clamped to `[0, 1]`.
+    pub fn epsilon(&self, iteration: usize, depth: usize) -> f64 {
+        let e = match *self {
+            Exploration::Constant(e) => e,
+            Exploration::Decaying { initial, rate, min } => {
+                (initial / (1.0 + rate * iteration as f64)).max(min)
+            }
+            Exploration::PerDepth { initial, factor } => initial * factor.powi(depth as i32),
+        };
+        e.clamp(0.0, 1.0)
+    }
+
+    fn is_valid(&self) -> bool {
+        let prob = |p: f64| (0.0..=1.0).contains(&p);
+        match *self {
+            Exploration::Constant(e) => prob(e),
+            Exploration::Decaying { initial, rate, min } => {
+                prob(initial) && prob(min) && rate >= 0.0
+            }
+            Exploration::PerDepth { initial, factor } => prob(initial) && factor >= 0.0,
+        }
+    }
+}
+
+/// The policy mixed into the updated player's regret-matching policy for exploration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExplorationPolicy {
+    Uniform,
+    /// The current average strategy (uniform in unvisited information sets).
+    /// Actions with zero probability in both policies are never sampled.
+    Average,
+}
+
+/// Sampling configuration of `OuterMCCFR`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MCCFRConfig {
+    pub exploration: Exploration,
+    pub policy: ExplorationPolicy,
+    pub chance: ChanceSampling,
+}
+
+impl MCCFRConfig {
+    pub fn new(
+        exploration: Exploration,
+        policy: ExplorationPolicy,
+        chance: ChanceSampling,
+    ) -> Self {
+        let config = MCCFRConfig {
+            exploration,
+            policy,
+            chance,
+        };
+        config.assert_valid();
+        config
+    }
+
+    /// Panics on probabilities outside `[0, 1]`, negative rates or factors, or sampling
+    /// no chance outcomes.
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.exploration.is_valid(),
+            "invalid exploration parameters {:?}",
+            self.exploration
+        );
+        assert!(
+            self.chance != ChanceSampling::WithoutReplacement(0),
+            "chance sampling needs at least one outcome"
+        );
+    }
+}
+
+impl Default for MCCFRConfig {
+    fn default() -> Self {
+        MCCFRConfig {
+            exploration: Exploration::Constant(0.6),
+            policy: ExplorationPolicy::Uniform,
+            chance: ChanceSampling::One,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct OuterMCCFR<G: Game> {
     pub game: G,
     pub iterations: usize,
     pub nodes_traversed: usize,
+    /// Total time spent iterating.
+    pub elapsed: Duration,
     pub strategies: Vec<RegretStrategy<G>>,
+    pub config: MCCFRConfig,
+    pub pruning: Option<Pruning>,
 }
 
 impl<G: Game> OuterMCCFR<G> {
@@ -20,119 +137,389 @@ impl<G: Game> OuterMCCFR<G> {
             game,
             iterations: 0,
             nodes_traversed: 0,
+            elapsed: Duration::default(),
             strategies: s,
+            config: MCCFRConfig::default(),
+            pruning: None,
         }
     }
 
+    pub fn with_config(mut self, config: MCCFRConfig) -> Self {
+        config.assert_valid();
+        self.config = config;
+        self
+    }
+
+    pub fn with_chance(mut self, chance: ChanceSampling) -> Self {
+        self.config.chance = chance;
+        self
+    }
+
+    pub fn with_pruning(mut self, pruning: Pruning) -> Self {
+        self.pruning = Some(pruning);
+        self
+    }
+
+    /// Run `iterations` iterations with constant exploration `epsilon` mixed with
+    /// the uniform policy (ignoring the configured exploration).
     pub fn compute_rng<R: Rng>(&mut self, iterations: usize, epsilon: f64, rng: &mut R) {
+        let config = MCCFRConfig {
+            exploration: Exploration::Constant(epsilon),
+            policy: ExplorationPolicy::Uniform,
+            ..self.config
+        };
+        self.compute_config_rng(iterations, &config, rng);
+    }
+
+    /// Run `iterations` iterations with the configuration in `self.config`.
+    pub fn iterate<R: Rng>(&mut self, iterations: usize, rng: &mut R) {
+        let config = self.config;
+        self.compute_config_rng(iterations, &config, rng);
+    }
+
+    fn compute_config_rng<R: Rng>(&mut self, iterations: usize, config: &MCCFRConfig, rng: &mut R) {
+        let start = Instant::now();
         for _i in 0..iterations {
+            let prune = self.pruning.and_then(|p| p.threshold(self.iterations));
             for player in 0..self.game.players() {
                 self.strategies[player].iterations += 1;
-                self.sample_rec(rng, player, self.game.start(), 1.0, 1.0, 1.0, epsilon);
+                let mut sampler = Sampler {
+                    tree: &self.game,
+                    tables: &mut self.strategies,
+                    rng: &mut *rng,
+                    config,
+                    iteration: self.iterations,
+                    updated: player,
+                    prune,
+                    nodes_traversed: 0,
+                };
+                sampler.sample(self.game.start(), 0, 1.0, 1.0, 1.0);
+                self.nodes_traversed += sampler.nodes_traversed;
             }
             self.iterations += 1;
         }
+        self.elapsed += start.elapsed();
+    }
+}
+
+/// A node of a `SampledTree` as seen by `Sampler`.
+pub(crate) enum SampledNode<'a, I> {
+    Terminal(&'a [Utility]),
+    /// Probabilities of the outcomes.
+    Chance(&'a [f64]),
+    /// The acting player, the information set and the number of actions.
+    Player(usize, I, usize),
+}
+
+/// A game tree traversed by `Sampler`: a `Game` with its histories or a `FlatTree`.
+pub(crate) trait SampledTree {
+    type Node;
+    type InfoSet;
+
+    fn sampled_node<'a>(&'a self, node: &'a Self::Node) -> SampledNode<'a, Self::InfoSet>;
+
+    fn child(&self, node: &Self::Node, action: usize) -> Self::Node;
+
+    /// As `child`, consuming the node.
+    fn child_owned(&self, node: Self::Node, action: usize) -> Self::Node {
+        self.child(&node, action)
+    }
+}
+
+impl<G: Game> SampledTree for G {
+    type Node = HistoryInfo<G>;
+    /// The player and its observations.
+    type InfoSet = (usize, Vec<PlayerObservation<G>>);
+
+    fn sampled_node<'a>(&'a self, node: &'a Self::Node) -> SampledNode<'a, Self::InfoSet> {
+        match node.active {
+            ActivePlayer::Terminal(ref u) => SampledNode::Terminal(u),
+            ActivePlayer::Chance(ref d) => SampledNode::Chance(d.probs()),
+            ActivePlayer::Player(p, ref actions) => {
+                let p = p as usize;
+                SampledNode::Player(p, (p, node.observations[p].clone()), actions.len())
+            }
+        }
+    }
+
+    fn child(&self, node: &Self::Node, action: usize) -> Self::Node {
+        self.play(node, action)
+    }
+
+    fn child_owned(&self, node: Self::Node, action: usize) -> Self::Node {
+        self.play_owned(node, action)
+    }
+}
+
+/// Cumulative regrets and average strategies updated by `Sampler`.
+pub(crate) trait RegretTables<I> {
+    /// The (average strategy, regret) sums of the information set, `None` if not stored.
+    fn entry(&self, infoset: &I) -> Option<(&[f64], &[f64])>;
+
+    fn update(&mut self, infoset: I, d_reg: Option<&[f64]>, d_strat: Option<&[f64]>);
+}
+
+impl<G: Game> RegretTables<(usize, Vec<PlayerObservation<G>>)> for Vec<RegretStrategy<G>> {
+    fn entry(&self, infoset: &(usize, Vec<PlayerObservation<G>>)) -> Option<(&[f64], &[f64])> {
+        self[infoset.0]
+            .table
+            .get(&infoset.1)
+            .map(|e| (&e.0[..], &e.1[..]))
     }
 
-    /// returns (utility, p_tail, p_sample_leaf)
-    fn sample_rec<R: Rng>(
+    fn update(
         &mut self,
-        rng: &mut R,
-        updated_player: usize,
-        hinfo: HistoryInfo<G>,
+        infoset: (usize, Vec<PlayerObservation<G>>),
+        d_reg: Option<&[f64]>,
+        d_strat: Option<&[f64]>,
+    ) {
+        self[infoset.0].update(infoset.1, d_reg, d_strat)
+    }
+}
+
+/// One outer sampling MCCFR traversal updating the regrets of `updated` and the average
+/// strategies of the other players, shared by `OuterMCCFR` and `FlatCFR`.
+pub(crate) struct Sampler<'a, T: SampledTree, B: RegretTables<T::InfoSet>, R: Rng> {
+    pub tree: &'a T,
+    pub tables: &'a mut B,
+    pub rng: &'a mut R,
+    pub config: &'a MCCFRConfig,
+    pub iteration: usize,
+    pub updated: usize,
+    /// Regret threshold below which zero-probability actions of `updated` are not explored.
+    pub prune: Option<f64>,
+    pub nodes_traversed: usize,
+}
+
+impl<'a, T: SampledTree, B: RegretTables<T::InfoSet>, R: Rng> Sampler<'a, T, B, R> {
+    /// Returns (utility * p_tail / p_sample_leaf, 1 / p_sample_leaf), both as expectations
+    /// over the traversed chance outcomes.
+    pub fn sample(
+        &mut self,
+        node: T::Node,
+        depth: usize,
         p_reach_updated: f64,
         p_reach_others: f64,
         p_sample: f64,
-        epsilon: f64,
-    ) -> (f64, f64, f64) {
+    ) -> (f64, f64) {
         self.nodes_traversed += 1;
-        match hinfo.active {
-            ActivePlayer::Terminal(ref payoffs) => (payoffs[updated_player], 1.0, p_sample),
-            ActivePlayer::Chance(ref cat) => {
-                let a = cat.sample_idx_rng(rng);
-                let nh = self.game.play_owned(hinfo, a);
-                self.sample_rec(
-                    rng,
-                    updated_player,
-                    nh,
-                    p_reach_updated,
-                    p_reach_others,
-                    p_sample,
-                    epsilon,
-                )
+        let tree = self.tree;
+        match tree.sampled_node(&node) {
+            SampledNode::Terminal(payoffs) => (payoffs[self.updated] / p_sample, 1.0 / p_sample),
+            SampledNode::Chance(probs) => {
+                let n = probs.len();
+                let outcomes: Vec<(usize, f64)> = match self.config.chance {
+                    ChanceSampling::One => {
+                        let a = crate::distribution::sample_weighted(probs, self.rng);
+                        let child = tree.child_owned(node, a);
+                        return self.sample(
+                            child,
+                            depth + 1,
+                            p_reach_updated,
+                            p_reach_others,
+                            p_sample,
+                        );
+                    }
+                    ChanceSampling::Enumerate => probs.iter().cloned().enumerate().collect(),
+                    ChanceSampling::WithoutReplacement(k) => {
+                        let k = k.max(1).min(n);
+                        rand::seq::index::sample(self.rng, n, k)
+                            .iter()
+                            .map(|i| (i, probs[i] * n as f64 / k as f64))
+                            .collect()
+                    }
+                };
+                let (mut value, mut inv_sample) = (0.0, 0.0);
+                for (i, w) in outcomes {
+                    let child = tree.child(&node, i);
+                    let (v, s) =
+                        self.sample(child, depth + 1, p_reach_updated, p_reach_others, p_sample);
+                    value += w * v;
+                    inv_sample += w * s;
+                }
+                (value, inv_sample)
             }
-            ActivePlayer::Player(player, ref actions) => {
-                let player = player as usize;
-                let n = actions.len();
-                let obs: Vec<PlayerObservation<G>> = hinfo.observations[player].clone();
-                let eps = if player == updated_player {
-                    epsilon
+            SampledNode::Player(player, infoset, n) => {
+                let eps = if player == self.updated {
+                    self.config.exploration.epsilon(self.iteration, depth)
                 } else {
                     0.0
                 };
-                let regret: Option<_> = self.strategies[player].table.get(&obs).map(|e| &e.1);
-                let dist = match regret {
-                    Some(r) => regret_matching(r),
+                let entry = self.tables.entry(&infoset);
+                let dist = match entry {
+                    Some(e) => regret_matching(e.1),
                     None => vec![1.0 / n as f64; n],
                 };
-                //let policy = self.strategies[player].policy(&hinfo.active, &obs); // regret matching!
+                // Exploration policy, `None` for uniform
+                let mut explore: Option<Vec<f64>> = match (self.config.policy, entry) {
+                    (ExplorationPolicy::Average, Some(e)) if e.0.iter().sum::<f64>() > 0.0 => {
+                        let total = e.0.iter().sum::<f64>();
+                        Some(e.0.iter().map(|v| v / total).collect())
+                    }
+                    _ => None,
+                };
+                // Pruned actions of the updated player are not explored (their regret updates
+                // are zero as they are never played)
+                match (self.prune, entry) {
+                    (Some(t), Some(e)) if player == self.updated => {
+                        let kept: Vec<bool> = dist
+                            .iter()
+                            .zip(e.1)
+                            .map(|(&p, &r)| p > 0.0 || r >= t)
+                            .collect();
+                        if kept.contains(&false) {
+                            let mut ex = explore.unwrap_or_else(|| vec![1.0; n]);
+                            for (v, &k) in ex.iter_mut().zip(&kept) {
+                                if !k {
+                                    *v = 0.0;
+                                }
+                            }
+                            if ex.iter().sum::<f64>() == 0.0 {
+                                ex = kept.iter().map(|&k| if k { 1.0 } else { 0.0 }).collect();
+                            }
+                            let total = ex.iter().sum::<f64>();
+                            ex.iter_mut().for_each(|v| *v /= total);
+                            explore = Some(ex);
+                        }
+                    }
+                    _ => (),
+                }
+                let rng = &mut *self.rng;
                 let a_sample = if rng.sample::<f64, _>(rand::distributions::Standard) < eps {
-                    rng.gen_range(0, n)
+                    match explore {
+                        Some(ref ex) => crate::distribution::sample_weighted(ex, rng),
+                        None => rng.gen_range(0, n),
+                    }
                 } else {
                     crate::distribution::sample_weighted(&dist, rng)
                 };
                 let p_dist = dist[a_sample];
-                let p_eps = eps / (n as f64) + (1.0 - eps) * p_dist;
-
-                let newinfo = self.game.play_owned(hinfo, a_sample);
-                if player == updated_player {
-                    let (payoff, p_tail, p_sample_leaf) = self.sample_rec(
-                        rng,
-                        updated_player,
-                        newinfo,
+                let p_explore = explore.map_or(1.0 / n as f64, |ex| ex[a_sample]);
+                let p_eps = eps * p_explore + (1.0 - eps) * p_dist;
+
+                let child = tree.child_owned(node, a_sample);
+                if player == self.updated {
+                    let (value, inv_sample) = self.sample(
+                        child,
+                        depth + 1,
                         p_reach_updated * p_dist,
                         p_reach_others,
                         p_sample * p_eps,
-                        epsilon,
                     );
                     let mut dr = vec![0.0; n];
-                    let u = payoff * p_reach_others / p_sample_leaf;
-                    for ai in 0..n {
+                    let u = value * p_reach_others;
+                    for (ai, d) in dr.iter_mut().enumerate() {
                         if ai == a_sample {
-                            dr[ai] = u * (p_tail - p_tail * p_dist);
+                            *d = u * (1.0 - p_dist);
                         } else {
-                            dr[ai] = -u * p_tail * p_dist;
+                            *d = -u * p_dist;
                         }
                     }
-                    self.strategies[player].update(obs.clone(), Some(&dr), None);
-                    (payoff, p_tail * p_dist, p_sample_leaf)
+                    self.tables.update(infoset, Some(&dr), None);
+                    (value * p_dist, inv_sample)
                 } else {
-                    let (payoff, p_tail, p_sample_leaf) = self.sample_rec(
-                        rng,
-                        updated_player,
-                        newinfo,
+                    let (value, inv_sample) = self.sample(
+                        child,
+                        depth + 1,
                         p_reach_updated,
                         p_reach_others * p_dist,
                         p_sample * p_eps,
-                        epsilon,
                     );
                     let mut ds = dist;
                     ds.iter_mut().for_each(|v| {
-                        *v *= p_reach_updated / p_sample_leaf;
+                        *v *= p_reach_updated * inv_sample;
                     });
-                    self.strategies[player].update(obs.clone(), None, Some(&ds));
-                    (payoff, p_tail * p_dist, p_sample_leaf)
+                    self.tables.update(infoset, None, Some(&ds));
+                    (value * p_dist, inv_sample)
                 }
             }
         }
     }
 }
 
+/// Plays the average strategy of the active player.
+impl<G: Game> Strategy<G> for OuterMCCFR<G> {
+    fn policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        match active.player() {
+            Some(p) => self.strategies[p].policy(active, obs),
+            None => panic!("strategy requested for non-player state {:?}", active),
+        }
+    }
+}
+
+impl<G: Game> Solver<G> for OuterMCCFR<G> {
+    type Tables = Vec<RegretStrategy<G>>;
+
+    fn iterate(&mut self, iterations: usize, mut rng: &mut dyn RngCore) {
+        OuterMCCFR::iterate(self, iterations, &mut rng)
+    }
+
+    fn current_policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        match active {
+            ActivePlayer::Player(p, ref actions) => {
+                self.strategies[*p as usize].current_policy(obs, actions.len())
+            }
+            _ => panic!("policy requested for non-player state {:?}", active),
+        }
+    }
+
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            iterations: self.iterations,
+            nodes_traversed: self.nodes_traversed,
+            infosets: self.strategies.iter().map(|s| s.infosets()).sum(),
+            elapsed: self.elapsed,
+        }
+    }
+
+    fn save(&self) -> SolverSnapshot<Self::Tables> {
+        SolverSnapshot {
+            iterations: self.iterations,
+            nodes_traversed: self.nodes_traversed,
+            elapsed: self.elapsed,
+            tables: self.strategies.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: SolverSnapshot<Self::Tables>) {
+        assert_eq!(snapshot.tables.len(), self.game.players());
+        self.iterations = snapshot.iterations;
+        self.nodes_traversed = snapshot.nodes_traversed;
+        self.elapsed = snapshot.elapsed;
+        self.strategies = snapshot.tables;
+    }
+
+    fn table_snapshot(&self) -> TableSnapshot {
+        let mut snapshot = TableSnapshot::new(self.iterations, self.nodes_traversed);
+        for (player, s) in self.strategies.iter().enumerate() {
+            for (obs, (strategy_sum, regrets)) in &s.table {
+                let entry = TableEntry {
+                    regrets: regrets.clone(),
+                    strategy_sum: strategy_sum.clone(),
+                };
+                snapshot.insert(player, obs, entry);
+            }
+        }
+        snapshot
+    }
+}
+
+/// Cumulative (strategy, regret) vectors of one information set.
+type RegretEntry = (Vec<f64>, Vec<f64>);
+
 #[derive(Clone, Debug)]
 pub struct RegretStrategy<G: Game> {
     pub updates: usize,
     pub iterations: usize,
-    table: HashMap<Vec<PlayerObservation<G>>, (Vec<f64>, Vec<f64>)>, // (strategy, regret)
+    table: HashMap<Vec<PlayerObservation<G>>, RegretEntry>,
     phantom: std::marker::PhantomData<G>,
 }
 
@@ -148,6 +535,24 @@ impl<G: Game> Default for RegretStrategy<G> {
 }
 
 impl<G: Game> RegretStrategy<G> {
+    /// Number of information sets with stored regrets.
+    pub fn infosets(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Regret-matching policy for the given number of actions, uniform when not stored.
+    pub fn current_policy(
+        &self,
+        obs: &[PlayerObservation<G>],
+        actions: usize,
+    ) -> Categorical<ActionIndex> {
+        let vs = (0..actions as ActionIndex).collect::<Vec<_>>();
+        match self.table.get(obs) {
+            None => Categorical::uniform(vs),
+            Some(d) => Categorical::new(regret_matching(&d.1), vs),
+        }
+    }
+
     pub fn update(
         &mut self,
         obs: Vec<PlayerObservation<G>>,
@@ -181,15 +586,14 @@ impl<G: Game> Strategy<G> for RegretStrategy<G> {
     fn policy(
         &self,
         active: &ActivePlayer<G>,
-        obs: &Vec<PlayerObservation<G>>,
+        obs: &[PlayerObservation<G>],
     ) -> Categorical<ActionIndex> {
         if let ActivePlayer::Player(_p, ref actions) = active {
-            println!("{:?} ", self.table.get(obs));
             match self.table.get(obs) {
                 None => Categorical::uniform((0..actions.len() as ActionIndex).collect::<Vec<_>>()),
-                Some(ref d) => {
+                Some(d) => {
                     let vs = (0..actions.len() as ActionIndex).collect::<Vec<_>>();
-                    let ps = &(*d).0 as &[_];
+                    let ps = &d.0 as &[_];
                     if ps.iter().sum::<f64>() < 1e-6 {
                         Categorical::uniform(vs)
                     } else {
@@ -206,7 +610,7 @@ impl<G: Game> Strategy<G> for RegretStrategy<G> {
     }
 }
 
-fn regret_matching(reg: &[f64]) -> Vec<f64> {
+pub(crate) fn regret_matching(reg: &[f64]) -> Vec<f64> {
     let regp = reg.iter().map(|&v| if v >= 0.0 { v } else { 0.0 });
     let s = regp.clone().sum::<f64>();
     let l = reg.len();
@@ -219,8 +623,15 @@ fn regret_matching(reg: &[f64]) -> Vec<f64> {
 
 #[cfg(test)]
 mod test {
-    use crate::{goofspiel, Game, Goofspiel, OuterMCCFR, Strategy};
+    use super::{ChanceSampling, Exploration, ExplorationPolicy, MCCFRConfig};
+    use crate::{
+        goofspiel, simulate, Budget, Game, Goofspiel, MatrixGame, OuterMCCFR, Pruning,
+        Sequentialized, Solver, Strategy,
+    };
     use rand::{rngs::SmallRng, SeedableRng};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_goof3_mccfr() {
@@ -236,4 +647,122 @@ mod test {
         let pol = mc.strategies[1].policy(&s.active, &s.observations[1]);
         assert!(pol.probs()[1] > 0.8);
     }
+
+    #[test]
+    fn test_seed_reproducible() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let run = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut mc = OuterMCCFR::new(g.clone());
+            mc.compute_rng(200, 0.6, &mut rng);
+            let t = simulate(&g, &[&mc, &mc], &mut rng);
+            (format!("{:?}", mc.strategies), t.actions().to_vec())
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7).0, run(8).0);
+    }
+
+    #[test]
+    fn test_chance_sampling() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        for (chance, iterations) in &[
+            (ChanceSampling::Enumerate, 1000),
+            (ChanceSampling::WithoutReplacement(2), 2000),
+        ] {
+            let mut mc = OuterMCCFR::new(g.clone()).with_chance(*chance);
+            mc.compute_rng(*iterations, 0.6, &mut SmallRng::seed_from_u64(1));
+            let s = g.play_owned(g.start(), 1);
+            let pol = mc.policy(&s.active, &s.observations[0]);
+            assert!(pol.probs()[1] > 0.8);
+        }
+    }
+
+    #[test]
+    fn test_exploration_config() {
+        let decay = Exploration::Decaying {
+            initial: 0.6,
+            rate: 0.01,
+            min: 0.1,
+        };
+        assert_eq!(decay.epsilon(0, 3), 0.6);
+        assert_eq!(decay.epsilon(100, 3), 0.3);
+        assert_eq!(decay.epsilon(10000, 3), 0.1);
+        let depth = Exploration::PerDepth {
+            initial: 0.8,
+            factor: 0.5,
+        };
+        assert_eq!(depth.epsilon(5, 2), 0.2);
+        let growing = Exploration::PerDepth {
+            initial: 0.5,
+            factor: 1.5,
+        };
+        assert_eq!(growing.epsilon(0, 1), 0.75);
+        assert_eq!(growing.epsilon(0, 2), 1.0);
+        assert_eq!(growing.epsilon(0, 10), 1.0);
+
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let config = MCCFRConfig {
+            exploration: decay,
+            policy: ExplorationPolicy::Average,
+            ..MCCFRConfig::default()
+        };
+        let mut mc = OuterMCCFR::new(g.clone()).with_config(config);
+        mc.iterate(5000, &mut SmallRng::seed_from_u64(1));
+        let s = g.play_owned(g.start(), 1);
+        let pol = mc.policy(&s.active, &s.observations[0]);
+        assert!(pol.probs()[1] > 0.8);
+    }
+
+    #[test]
+    fn test_pruning() {
+        // The second row is dominated, its regret stops changing once pruned
+        let g = Sequentialized::new(MatrixGame::zero_sum(&[vec![1.0, 0.0], vec![-5.0, -5.0]]));
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut mc = OuterMCCFR::new(g.clone()).with_pruning(Pruning::new(-1.0, 20, 100));
+        let regret = |mc: &OuterMCCFR<_>| mc.strategies[0].table[&vec![]].1[1];
+        mc.compute_rng(60, 0.6, &mut rng);
+        let r = regret(&mc);
+        assert!(r < -1.0);
+        mc.compute_rng(40, 0.6, &mut rng);
+        assert_eq!(regret(&mc), r);
+        // Revisit iterations explore it again
+        mc.compute_rng(300, 0.6, &mut rng);
+        assert!(regret(&mc) < r);
+        let s = g.start();
+        assert!(mc.policy(&s.active, &s.observations[0]).probs()[0] > 0.9);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid exploration parameters")]
+    fn test_invalid_config() {
+        let decay = Exploration::Decaying {
+            initial: 1.5,
+            rate: 0.01,
+            min: 0.1,
+        };
+        MCCFRConfig::new(decay, ExplorationPolicy::Uniform, ChanceSampling::One);
+    }
+
+    #[test]
+    fn test_budgets() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut mc = OuterMCCFR::new(g);
+        assert_eq!(mc.compute_budget(&Budget::iterations(7), &mut rng), 7);
+        mc.compute_until(1000, &mut rng);
+        assert!(mc.nodes_traversed >= 1000);
+        assert_eq!(mc.compute_until(1000, &mut rng), 0);
+        let start = Instant::now();
+        assert!(mc.compute_for(Duration::from_millis(20), &mut rng) > 0);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // A raised stop flag ends the run, the solver resumes after clearing it
+        let stop = Arc::new(AtomicBool::new(true));
+        let budget = Budget::iterations(5).with_stop(stop.clone());
+        let it = mc.iterations;
+        assert_eq!(mc.compute_budget(&budget, &mut rng), 0);
+        stop.store(false, Ordering::Relaxed);
+        assert_eq!(mc.compute_budget(&budget, &mut rng), 5);
+        assert_eq!(mc.iterations, it + 5);
+    }
 }
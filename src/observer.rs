@@ -0,0 +1,137 @@
+use crate::{exploitability, Game, Strategy};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Progress of a solver reported to observers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverStats {
+    pub iterations: usize,
+    pub nodes_traversed: usize,
+    /// Number of information sets with stored regrets.
+    pub infosets: usize,
+    /// Total wall-clock time the solver spent iterating.
+    pub elapsed: Duration,
+}
+
+/// Called by solvers every few iterations with the current statistics and the solver's
+/// (average) strategy, e.g. to record learning curves.
+pub trait SolverObserver<G: Game> {
+    fn observe(&mut self, stats: &SolverStats, strategy: &dyn Strategy<G>);
+}
+
+impl<G: Game, F: FnMut(&SolverStats, &dyn Strategy<G>)> SolverObserver<G> for F {
+    fn observe(&mut self, stats: &SolverStats, strategy: &dyn Strategy<G>) {
+        self(stats, strategy)
+    }
+}
+
+/// Writes one CSV line per observation:
+/// `iterations,nodes_traversed,infosets,seconds,exploitability`.
+///
+/// The exploitability column is only filled with `with_exploitability` (the evaluation
+/// traverses the whole game tree on every observation).
+pub struct CsvLogger<G: Game, W: Write> {
+    writer: W,
+    game: Option<G>,
+    header: bool,
+    /// The first write error, further writes are skipped.
+    pub error: Option<io::Error>,
+}
+
+impl<G: Game, W: Write> CsvLogger<G, W> {
+    pub fn new(writer: W) -> Self {
+        CsvLogger {
+            writer,
+            game: None,
+            header: false,
+            error: None,
+        }
+    }
+
+    /// Also evaluate the exploitability of the strategy in `game` (two-player zero-sum).
+    pub fn with_exploitability(mut self, game: G) -> Self {
+        self.game = Some(game);
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write(&mut self, stats: &SolverStats, strategy: &dyn Strategy<G>) -> io::Result<()> {
+        if !self.header {
+            writeln!(
+                self.writer,
+                "iterations,nodes_traversed,infosets,seconds,exploitability"
+            )?;
+            self.header = true;
+        }
+        let expl = match self.game {
+            Some(ref g) => exploitability(g, strategy).to_string(),
+            None => String::new(),
+        };
+        writeln!(
+            self.writer,
+            "{},{},{},{},{}",
+            stats.iterations,
+            stats.nodes_traversed,
+            stats.infosets,
+            stats.elapsed.as_secs_f64(),
+            expl
+        )
+    }
+}
+
+impl<G: Game, W: Write> SolverObserver<G> for CsvLogger<G, W> {
+    fn observe(&mut self, stats: &SolverStats, strategy: &dyn Strategy<G>) {
+        if self.error.is_none() {
+            if let Err(e) = self.write(stats, strategy) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CsvLogger, SolverStats};
+    use crate::{goofspiel, Goofspiel, OuterMCCFR, Solver, Strategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_observers() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let mut mc = OuterMCCFR::new(g.clone());
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut seen = Vec::new();
+        mc.iterate_observed(
+            25,
+            10,
+            &mut |s: &SolverStats, _: &dyn Strategy<Goofspiel>| seen.push(s.iterations),
+            &mut rng,
+        );
+        assert_eq!(seen, vec![10, 20, 25]);
+        // Without iterations the observer still sees the final state once
+        mc.iterate_observed(
+            0,
+            10,
+            &mut |s: &SolverStats, _: &dyn Strategy<Goofspiel>| seen.push(s.iterations),
+            &mut rng,
+        );
+        assert_eq!(seen, vec![10, 20, 25, 25]);
+
+        let mut log = CsvLogger::new(Vec::new()).with_exploitability(g);
+        mc.iterate_observed(20, 10, &mut log, &mut rng);
+        assert!(log.error.is_none());
+        let csv = String::from_utf8(log.into_inner()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "iterations,nodes_traversed,infosets,seconds,exploitability"
+        );
+        let cols: Vec<_> = lines[2].split(',').collect();
+        assert_eq!(cols[0], "45");
+        assert!(cols[4].parse::<f64>().unwrap() > 0.0);
+    }
+}
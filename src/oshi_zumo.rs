@@ -0,0 +1,152 @@
+use crate::{ActivePlayer, Game, HistoryInfo};
+
+/// Oshi-Zumo, a simultaneous-move coin bidding game.
+///
+/// Both players start with `coins` coins and a wrestler stands in the middle of a board of
+/// `2 * size + 1` positions. In every round both players secretly bid at least `min_bid`
+/// coins (or 0 when they have less), the bids are paid and the higher bidder pushes the wrestler
+/// one step towards the opponent (player 0 pushes to higher positions). The game ends when
+/// the wrestler leaves the board, when no player can bid anymore or after `horizon` rounds.
+/// Player 0 wins (utility 1) if the wrestler ends on player 1's side and vice versa.
+///
+/// The game tree grows exponentially with `horizon`. With `min_bid = 0` the players can
+/// bid 0 for all the rounds, so only small horizons are usable with full-tree tools.
+///
+/// Simultaneous moves are sequentialized as in `Goofspiel`: player 0 bids first, player 1
+/// bids without observing it, then both bids are revealed to everyone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OshiZumo {
+    pub coins: u32,
+    pub size: u32,
+    pub min_bid: u32,
+    /// Maximal number of rounds.
+    pub horizon: usize,
+}
+
+impl OshiZumo {
+    pub fn new(coins: u32, size: u32, min_bid: u32, horizon: usize) -> Self {
+        assert!(horizon >= 1, "Oshi-Zumo needs at least one round");
+        OshiZumo {
+            coins,
+            size,
+            min_bid,
+            horizon,
+        }
+    }
+
+    fn bids(&self, coins: u32) -> Vec<u32> {
+        if coins >= self.min_bid {
+            (self.min_bid..=coins).collect()
+        } else {
+            vec![0]
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct State {
+    coins: [u32; 2],
+    /// Wrestler position in `0..=2 * size`, outside when pushed off.
+    position: i32,
+}
+
+impl Game for OshiZumo {
+    type State = State;
+    /// Both bids of a round.
+    type Observation = (u32, u32);
+    type Action = u32;
+
+    fn players(&self) -> usize {
+        2
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let state = State {
+            coins: [self.coins, self.coins],
+            position: self.size as i32,
+        };
+        (state, ActivePlayer::Player(0, self.bids(self.coins)))
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let history = &hist.history;
+        let mut state = hist.state.clone();
+        if hist.active.player() == Some(0) {
+            // Player 0 bid, hidden until player 1 bids
+            let active = ActivePlayer::Player(1, self.bids(state.coins[1]));
+            return (state, active, vec![None; 3]);
+        }
+        let (b0, b1) = (history[history.len() - 1], *action);
+        state.coins[0] -= b0;
+        state.coins[1] -= b1;
+        if b0 > b1 {
+            state.position += 1;
+        } else if b1 > b0 {
+            state.position -= 1;
+        }
+        let rounds = history.len() / 2 + 1;
+        let can_bid = |c: u32| c >= self.min_bid.max(1);
+        let active = if state.position < 0
+            || state.position > 2 * self.size as i32
+            || rounds >= self.horizon
+            || !(can_bid(state.coins[0]) || can_bid(state.coins[1]))
+        {
+            let d = (state.position - self.size as i32).signum() as f64;
+            ActivePlayer::Terminal(vec![d, -d])
+        } else {
+            ActivePlayer::Player(0, self.bids(state.coins[0]))
+        };
+        (state, active, vec![Some((b0, b1)); 3])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OshiZumo;
+    use crate::Observation::*;
+    use crate::{check_perfect_recall, ActivePlayer, Game};
+
+    #[test]
+    fn test_oshi_zumo() {
+        let g = OshiZumo::new(5, 1, 1, 10);
+        let mut h = g.start();
+        assert_eq!(h.active, ActivePlayer::Player(0, vec![1, 2, 3, 4, 5]));
+        for a in &[2, 1, 1, 1, 2] {
+            h = g.play_value(&h, a);
+        }
+        assert_eq!(h.active, ActivePlayer::Player(1, vec![1, 2, 3]));
+        h = g.play_value(&h, &1);
+        // Wrestler pushed off player 1's edge
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![1.0, -1.0]));
+        assert_eq!(
+            h.observations[1],
+            vec![
+                Own(1),
+                Obs((2, 1)),
+                Own(1),
+                Obs((1, 1)),
+                Own(1),
+                Obs((2, 1))
+            ]
+        );
+        assert!(check_perfect_recall(&OshiZumo::new(3, 1, 1, 10)).is_ok());
+        // Running out of coins in the middle is a draw
+        let g = OshiZumo::new(1, 2, 1, 10);
+        let h = g.play_value(&g.play_value(&g.start(), &1), &1);
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![0.0, 0.0]));
+        // Zero bids only end at the horizon
+        let g = OshiZumo::new(2, 1, 0, 2);
+        let h = g.play_value(&g.play_value(&g.start(), &0), &0);
+        assert_eq!(h.active, ActivePlayer::Player(0, vec![0, 1, 2]));
+        let h = g.play_value(&g.play_value(&h, &0), &0);
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![0.0, 0.0]));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{Game, HistoryInfo, PlayerObservation};
+use hashbrown::HashMap;
+
+/// The key of a public state: the observations of the public observer
+/// (slot `players()` of `HistoryInfo::observations`).
+#[allow(type_alias_bounds)]
+pub type PublicStateKey<G: Game> = Vec<PlayerObservation<G>>;
+
+/// All histories sharing the same public observations.
+///
+/// Histories are listed in depth-first order, so every history comes after its
+/// predecessors within the same public state.
+#[derive(Clone, Debug)]
+pub struct PublicState<G: Game> {
+    pub key: PublicStateKey<G>,
+    pub histories: Vec<HistoryInfo<G>>,
+    /// Indices into `histories` of the histories whose parent is in a different public state.
+    pub roots: Vec<usize>,
+}
+
+impl<G: Game> PublicState<G> {
+    fn new(key: PublicStateKey<G>) -> Self {
+        PublicState {
+            key,
+            histories: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// The belief range of `player` in this public state: the information sets
+    /// (observation sequences) the player can be in, each with the indices of its histories.
+    /// Information sets are listed in the order of their first history.
+    pub fn range(&self, player: usize) -> Vec<(Vec<PlayerObservation<G>>, Vec<usize>)> {
+        let mut index: HashMap<&[PlayerObservation<G>], usize> = HashMap::new();
+        let mut range: Vec<(Vec<PlayerObservation<G>>, Vec<usize>)> = Vec::new();
+        for (hi, h) in self.histories.iter().enumerate() {
+            let obs = &h.observations[player] as &[_];
+            let ri = *index.entry(obs).or_insert_with(|| {
+                range.push((obs.to_vec(), Vec::new()));
+                range.len() - 1
+            });
+            range[ri].1.push(hi);
+        }
+        range
+    }
+}
+
+/// Enumerate all public states of the game by traversing the whole game tree.
+/// Public states are listed in the order of their first history.
+pub fn public_states<G: Game>(game: &G) -> Vec<PublicState<G>> {
+    let mut index: HashMap<PublicStateKey<G>, usize> = HashMap::new();
+    let mut states = Vec::new();
+    collect_all(game, game.start(), true, &mut index, &mut states);
+    states
+}
+
+fn collect_all<G: Game>(
+    game: &G,
+    hist: HistoryInfo<G>,
+    is_root: bool,
+    index: &mut HashMap<PublicStateKey<G>, usize>,
+    states: &mut Vec<PublicState<G>>,
+) {
+    let key = hist.public_state_key().to_vec();
+    let si = match index.get(&key) {
+        Some(&si) => si,
+        None => {
+            states.push(PublicState::new(key.clone()));
+            index.insert(key.clone(), states.len() - 1);
+            states.len() - 1
+        }
+    };
+    let s = &mut states[si];
+    if is_root {
+        s.roots.push(s.histories.len());
+    }
+    s.histories.push(hist.clone());
+    for i in 0..hist.active.actions().len() {
+        let h2 = game.play(&hist, i);
+        let root = h2.public_state_key().len() != key.len();
+        collect_all(game, h2, root, index, states);
+    }
+}
+
+/// Enumerate the histories of the public state with the given key.
+///
+/// Only the subtrees consistent with `key` are traversed.
+pub fn public_state<G: Game>(game: &G, key: &[PlayerObservation<G>]) -> PublicState<G> {
+    let mut state = PublicState::new(key.to_vec());
+    collect_key(game, game.start(), true, &mut state);
+    state
+}
+
+fn collect_key<G: Game>(game: &G, hist: HistoryInfo<G>, is_root: bool, state: &mut PublicState<G>) {
+    let hkey = hist.public_state_key();
+    if hkey.len() > state.key.len() || hkey != &state.key[..hkey.len()] {
+        return;
+    }
+    let matches = hkey.len() == state.key.len();
+    if matches {
+        if is_root {
+            state.roots.push(state.histories.len());
+        }
+        state.histories.push(hist.clone());
+    }
+    for i in 0..hist.active.actions().len() {
+        let h2 = game.play(&hist, i);
+        let root = !matches || h2.public_state_key().len() != hkey.len();
+        collect_key(game, h2, root, state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{public_state, public_states};
+    use crate::{goofspiel, Game, Goofspiel, Observation::*};
+
+    #[test]
+    fn test_goofspiel_public_states() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let all = public_states(&g);
+        assert_eq!(all[0].key, vec![]);
+        assert_eq!(all[0].histories.len(), 1);
+        let s = public_state(&g, &[Obs(goofspiel::Info::Card(2))]);
+        assert_eq!(s.histories.len(), 4);
+        assert_eq!(s.roots, vec![0]);
+        assert_eq!(s.range(0).len(), 4);
+        assert_eq!(s.range(1).len(), 1);
+        assert_eq!(s.range(1)[0].1, vec![0, 1, 2, 3]);
+        let t = all.iter().find(|t| t.key == s.key).unwrap();
+        assert_eq!(t.histories.len(), 4);
+        let h = g.play_value(&g.play_value(&g.start(), &2), &3);
+        assert_eq!(h.public_state_key(), &[Obs(goofspiel::Info::Card(2))]);
+    }
+}
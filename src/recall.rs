@@ -0,0 +1,282 @@
+use crate::{ActionIndex, ActivePlayer, Game, HistoryInfo, PlayerObservation};
+use hashbrown::HashMap;
+
+/// A witness that a game does not have perfect recall: two histories
+/// with the same observation sequence of `player` that differ in what
+/// the player did before or in what the player may do now.
+#[derive(Clone, Debug)]
+pub enum RecallViolation<G: Game> {
+    /// The player's own past actions differ.
+    OwnActions {
+        player: usize,
+        observations: Vec<PlayerObservation<G>>,
+        first: Vec<G::Action>,
+        second: Vec<G::Action>,
+    },
+    /// The available actions differ.
+    ActionSets {
+        player: usize,
+        observations: Vec<PlayerObservation<G>>,
+        first: Vec<G::Action>,
+        second: Vec<G::Action>,
+    },
+}
+
+/// Per-player map from the observation sequence to (own past actions, available actions).
+type RecallTable<G> =
+    HashMap<Vec<PlayerObservation<G>>, (Vec<<G as Game>::Action>, Vec<<G as Game>::Action>)>;
+
+/// Check that `game` has perfect recall by traversing the whole game tree.
+///
+/// All decision histories of a player sharing the player's observation sequence
+/// must also share the player's own action sequence and the set of available actions.
+/// Returns the first violation found.
+pub fn check_perfect_recall<G: Game>(game: &G) -> Result<(), RecallViolation<G>> {
+    let mut tables: Vec<RecallTable<G>> = vec![HashMap::new(); game.players()];
+    let mut own = vec![Vec::new(); game.players()];
+    check_rec(game, &game.start(), &mut own, &mut tables)
+}
+
+fn check_rec<G: Game>(
+    game: &G,
+    hist: &HistoryInfo<G>,
+    own: &mut Vec<Vec<G::Action>>,
+    tables: &mut Vec<RecallTable<G>>,
+) -> Result<(), RecallViolation<G>> {
+    if let ActivePlayer::Terminal(_) = hist.active {
+        return Ok(());
+    }
+    if let ActivePlayer::Player(p, ref actions) = hist.active {
+        let p = p as usize;
+        let obs = &hist.observations[p];
+        match tables[p].get(obs) {
+            None => {
+                tables[p].insert(obs.clone(), (own[p].clone(), actions.clone()));
+            }
+            Some((o_own, o_actions)) => {
+                if o_own != &own[p] {
+                    return Err(RecallViolation::OwnActions {
+                        player: p,
+                        observations: obs.clone(),
+                        first: o_own.clone(),
+                        second: own[p].clone(),
+                    });
+                }
+                if o_actions != actions {
+                    return Err(RecallViolation::ActionSets {
+                        player: p,
+                        observations: obs.clone(),
+                        first: o_actions.clone(),
+                        second: actions.clone(),
+                    });
+                }
+            }
+        }
+    }
+    let player = hist.active.player();
+    for (i, a) in hist.active.actions().iter().enumerate() {
+        if let Some(p) = player {
+            own[p].push(a.clone());
+        }
+        let res = check_rec(game, &game.play(hist, i), own, tables);
+        if let Some(p) = player {
+            own[p].pop();
+        }
+        res?;
+    }
+    Ok(())
+}
+
+/// A witness that two games with the same tree shape induce different information sets.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PartitionMismatch {
+    /// The games differ in the active player, the number of actions, chance probabilities
+    /// or utilities after the given action indices.
+    Structure { history: Vec<ActionIndex> },
+    /// Two decision histories of `player` in one information set of the first game are in
+    /// different information sets of the second game.
+    Split {
+        player: usize,
+        first: Vec<ActionIndex>,
+        second: Vec<ActionIndex>,
+    },
+    /// Two decision histories of `player` in different information sets of the first game
+    /// share an information set of the second game.
+    Merged {
+        player: usize,
+        first: Vec<ActionIndex>,
+        second: Vec<ActionIndex>,
+    },
+}
+
+/// Per-player map from an observation sequence in one game to the corresponding
+/// sequence in the other game and the first history with it.
+type PartitionTable<A, B> =
+    HashMap<Vec<PlayerObservation<A>>, (Vec<PlayerObservation<B>>, Vec<ActionIndex>)>;
+
+/// Check that games `a` and `b` have the same tree (compared by action indices) and the
+/// same information set partition of every player, e.g. that a `TreeGame` copy is faithful
+/// to its source game. Traverses both whole trees and returns the first difference found.
+pub fn check_same_infosets<A: Game, B: Game>(a: &A, b: &B) -> Result<(), PartitionMismatch> {
+    let mut fwd: Vec<PartitionTable<A, B>> = vec![HashMap::new(); a.players()];
+    let mut bwd: Vec<PartitionTable<B, A>> = vec![HashMap::new(); a.players()];
+    if a.players() != b.players() {
+        return Err(PartitionMismatch::Structure {
+            history: Vec::new(),
+        });
+    }
+    same_rec(a, b, &a.start(), &b.start(), &mut fwd, &mut bwd)
+}
+
+fn same_rec<A: Game, B: Game>(
+    a: &A,
+    b: &B,
+    ha: &HistoryInfo<A>,
+    hb: &HistoryInfo<B>,
+    fwd: &mut Vec<PartitionTable<A, B>>,
+    bwd: &mut Vec<PartitionTable<B, A>>,
+) -> Result<(), PartitionMismatch> {
+    let same = match (&ha.active, &hb.active) {
+        (ActivePlayer::Terminal(ua), ActivePlayer::Terminal(ub)) => ua == ub,
+        (ActivePlayer::Chance(da), ActivePlayer::Chance(db)) => da.probs() == db.probs(),
+        (ActivePlayer::Player(pa, aa), ActivePlayer::Player(pb, ab)) => {
+            pa == pb && aa.len() == ab.len()
+        }
+        _ => false,
+    };
+    if !same {
+        return Err(PartitionMismatch::Structure {
+            history: ha.history_indices.clone(),
+        });
+    }
+    if let Some(p) = ha.active.player() {
+        let (oa, ob) = (&ha.observations[p], &hb.observations[p]);
+        let here = &ha.history_indices;
+        if let Some((o, first)) = fwd[p].get(oa) {
+            if o != ob {
+                return Err(PartitionMismatch::Split {
+                    player: p,
+                    first: first.clone(),
+                    second: here.clone(),
+                });
+            }
+        }
+        if let Some((o, first)) = bwd[p].get(ob) {
+            if o != oa {
+                return Err(PartitionMismatch::Merged {
+                    player: p,
+                    first: first.clone(),
+                    second: here.clone(),
+                });
+            }
+        }
+        fwd[p].insert(oa.clone(), (ob.clone(), here.clone()));
+        bwd[p].insert(ob.clone(), (oa.clone(), here.clone()));
+    }
+    for i in 0..ha.active.actions().len() {
+        same_rec(a, b, &a.play(ha, i), &b.play(hb, i), fwd, bwd)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_perfect_recall, check_same_infosets, PartitionMismatch, RecallViolation};
+    use crate::{goofspiel, ActivePlayer, Game, Goofspiel, HistoryInfo, LazyTreeGame};
+    use crate::{TreeGame, TreeGameBuilder};
+
+    /// Player 0 moves twice but does not observe its first move.
+    #[derive(Clone, Debug)]
+    struct Forgetful;
+
+    impl Game for Forgetful {
+        type State = ();
+        type Observation = ();
+        type Action = u32;
+
+        fn players(&self) -> usize {
+            1
+        }
+
+        fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+            ((), ActivePlayer::Player(0, vec![0, 1]))
+        }
+
+        fn update_state(
+            &self,
+            hist: &HistoryInfo<Self>,
+            _action: &Self::Action,
+        ) -> (Self::State, ActivePlayer<Self>, Vec<Option<()>>) {
+            let active = if hist.history.is_empty() {
+                ActivePlayer::Player(0, vec![0, 1])
+            } else {
+                ActivePlayer::Terminal(vec![0.0])
+            };
+            ((), active, vec![None; 2])
+        }
+
+        fn play_owned(&self, hist: HistoryInfo<Self>, action_index: usize) -> HistoryInfo<Self> {
+            let action = hist.active.actions()[action_index];
+            let (state, active, _) = self.update_state(&hist, &action);
+            let mut history = hist.history;
+            let mut history_indices = hist.history_indices;
+            history.push(action);
+            history_indices.push(action_index as u32);
+            HistoryInfo {
+                state,
+                active,
+                history,
+                history_indices,
+                observations: hist.observations,
+            }
+        }
+    }
+
+    #[test]
+    fn test_perfect_recall() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        assert!(check_perfect_recall(&g).is_ok());
+        assert!(check_perfect_recall(&TreeGame::from_game(&g)).is_ok());
+        match check_perfect_recall(&Forgetful) {
+            Err(RecallViolation::OwnActions { player: 0, .. }) => (),
+            r => panic!("expected an own-action violation, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_same_infosets() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        assert_eq!(check_same_infosets(&g, &TreeGame::from_game(&g)), Ok(()));
+        let bids = g.clone().with_reveal(goofspiel::Reveal::Bids);
+        match check_same_infosets(&g, &bids) {
+            Err(PartitionMismatch::Split { .. }) => (),
+            r => panic!("expected a split information set, got {:?}", r),
+        }
+        match check_same_infosets(&bids, &g) {
+            Err(PartitionMismatch::Merged { .. }) => (),
+            r => panic!("expected merged information sets, got {:?}", r),
+        }
+        assert!(check_same_infosets(&g, &Goofspiel::new(2, goofspiel::Scoring::ZeroSum)).is_err());
+
+        // Both players privately observe different cards in the same step
+        let mut b = TreeGameBuilder::new(2);
+        let root = b.chance();
+        for (c0, c1) in &[("J", "K"), ("K", "J")] {
+            let n = b.player(0);
+            b.outcome(root, c0, 0.5, n)
+                .observe(n, 0, *c0)
+                .observe(n, 1, *c1);
+            for a in &["x", "y"] {
+                let t = b.terminal(vec![0.0, 0.0]);
+                b.action(n, a, t);
+            }
+        }
+        let t = b.build(root).unwrap();
+        assert_eq!(check_same_infosets(&t, &TreeGame::from_game(&t)), Ok(()));
+        // Wrappers playing through `update_state` see the same observations
+        assert_eq!(
+            check_same_infosets(&t, &LazyTreeGame::new(t.clone())),
+            Ok(())
+        );
+    }
+}
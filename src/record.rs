@@ -0,0 +1,594 @@
+//! Trajectory logs in JSON lines format.
+//!
+//! Each line records one trajectory as a JSON object:
+//!
+//! ```text
+//! {"game":"<game spec>","indices":[..],"actions":[..],"observations":[[..],..],"utilities":[..]}
+//! ```
+//!
+//! The game spec is a string chosen by the caller (e.g. `"goofspiel-4-zerosum"`) identifying
+//! the game and its parameters. Actions are replayed by their indices, the actions and the
+//! observations of every player and the public observer are also stored as their `Debug`
+//! strings, so the format does not depend on the representation of the game types and
+//! `replay` can verify them one by one, together with the utilities.
+//! Utilities must be finite.
+
+use crate::{ActivePlayer, Game, HistoryInfo, Trajectory, Utility};
+use std::fmt::{self, Write as FmtWrite};
+use std::io::{self, BufRead, Write};
+
+/// One parsed line of a trajectory log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrajectoryRecord {
+    pub game: String,
+    pub indices: Vec<usize>,
+    /// `Debug` representations of the actions.
+    pub actions: Vec<String>,
+    /// `Debug` representations of the observations of every player and the public observer.
+    pub observations: Vec<Vec<String>>,
+    pub utilities: Vec<Utility>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    GameMismatch {
+        recorded: String,
+        found: String,
+    },
+    InvalidAction {
+        step: usize,
+        index: usize,
+    },
+    ActionMismatch {
+        step: usize,
+        recorded: Option<String>,
+        found: Option<String>,
+    },
+    /// The first differing observation `index` of `player` (`None` if missing).
+    ObservationMismatch {
+        player: usize,
+        index: usize,
+        recorded: Option<String>,
+        found: Option<String>,
+    },
+    UtilityMismatch {
+        recorded: Vec<Utility>,
+        found: Vec<Utility>,
+    },
+    NonFiniteUtility {
+        player: usize,
+    },
+    NotTerminal,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(e) => write!(f, "I/O error: {}", e),
+            RecordError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            RecordError::GameMismatch { recorded, found } => {
+                write!(f, "recorded game {} differs from {}", recorded, found)
+            }
+            RecordError::InvalidAction { step, index } => {
+                write!(f, "step {}: action index {} not available", step, index)
+            }
+            RecordError::ActionMismatch {
+                step,
+                recorded,
+                found,
+            } => write!(
+                f,
+                "step {}: recorded action {:?} differs from {:?}",
+                step, recorded, found
+            ),
+            RecordError::ObservationMismatch {
+                player,
+                index,
+                recorded,
+                found,
+            } => write!(
+                f,
+                "observation {} of player {}: recorded {:?} differs from {:?}",
+                index, player, recorded, found
+            ),
+            RecordError::UtilityMismatch { recorded, found } => {
+                write!(
+                    f,
+                    "recorded utilities {:?} differ from {:?}",
+                    recorded, found
+                )
+            }
+            RecordError::NonFiniteUtility { player } => {
+                write!(f, "utility of player {} is not finite", player)
+            }
+            RecordError::NotTerminal => write!(f, "replayed history is not terminal"),
+        }
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        RecordError::Io(e)
+    }
+}
+
+impl TrajectoryRecord {
+    /// Record a terminal history of the game identified by `spec`.
+    pub fn from_history<G: Game>(spec: &str, hist: &HistoryInfo<G>) -> Self {
+        let utilities = match hist.active {
+            ActivePlayer::Terminal(ref u) => u.clone(),
+            _ => panic!("recording non-terminal history {:?}", hist),
+        };
+        TrajectoryRecord {
+            game: spec.to_string(),
+            indices: hist.history_indices.iter().map(|&i| i as usize).collect(),
+            actions: debug_strings(&hist.history),
+            observations: hist.observations.iter().map(|o| debug_strings(o)).collect(),
+            utilities,
+        }
+    }
+
+    pub fn from_trajectory<G: Game>(spec: &str, trajectory: &Trajectory<G>) -> Self {
+        Self::from_history(spec, trajectory.last())
+    }
+
+    /// Encode as a single JSON line (without the newline). Fails on non-finite utilities,
+    /// which have no JSON representation.
+    pub fn to_json(&self) -> Result<String, RecordError> {
+        if let Some(player) = self.utilities.iter().position(|u| !u.is_finite()) {
+            return Err(RecordError::NonFiniteUtility { player });
+        }
+        let mut s = String::new();
+        s.push_str("{\"game\":");
+        write_json_str(&mut s, &self.game);
+        s.push_str(",\"indices\":");
+        write_json_nums(&mut s, &self.indices);
+        s.push_str(",\"actions\":");
+        write_json_strs(&mut s, &self.actions);
+        s.push_str(",\"observations\":[");
+        for (i, os) in self.observations.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            write_json_strs(&mut s, os);
+        }
+        s.push(']');
+        s.push_str(",\"utilities\":");
+        write_json_nums(&mut s, &self.utilities);
+        s.push('}');
+        Ok(s)
+    }
+
+    /// Decode a JSON line produced by `to_json`.
+    pub fn from_json(line: &str) -> Result<Self, String> {
+        let mut rec = TrajectoryRecord {
+            game: String::new(),
+            indices: Vec::new(),
+            actions: Vec::new(),
+            observations: Vec::new(),
+            utilities: Vec::new(),
+        };
+        Parser::object(line, |p, key| {
+            match key {
+                "game" => rec.game = p.string()?,
+                "indices" => rec.indices = p.array(|p| p.parse("index"))?,
+                "actions" => rec.actions = p.array(Parser::string)?,
+                "observations" => rec.observations = p.array(|p| p.array(Parser::string))?,
+                "utilities" => rec.utilities = p.array(|p| p.parse("utility"))?,
+                k => return Err(format!("unknown key {:?}", k)),
+            }
+            Ok(())
+        })?;
+        Ok(rec)
+    }
+
+    /// Re-play the recorded action indices in `game` (identified by `spec`), verifying the
+    /// game spec, actions, observations and utilities against the record.
+    /// Returns the terminal history.
+    pub fn replay<G: Game>(&self, game: &G, spec: &str) -> Result<HistoryInfo<G>, RecordError> {
+        if spec != self.game {
+            return Err(RecordError::GameMismatch {
+                recorded: self.game.clone(),
+                found: spec.to_string(),
+            });
+        }
+        let mut hist = game.start();
+        for (step, &ai) in self.indices.iter().enumerate() {
+            if ai >= hist.active.actions().len() {
+                return Err(RecordError::InvalidAction { step, index: ai });
+            }
+            hist = game.play_owned(hist, ai);
+        }
+        let actions = debug_strings(&hist.history);
+        if let Some(step) = first_difference(&self.actions, &actions) {
+            return Err(RecordError::ActionMismatch {
+                step,
+                recorded: self.actions.get(step).cloned(),
+                found: actions.get(step).cloned(),
+            });
+        }
+        for player in 0..hist.observations.len().max(self.observations.len()) {
+            let recorded = self.observations.get(player).map_or(&[][..], |o| &o[..]);
+            let found = hist
+                .observations
+                .get(player)
+                .map_or(Vec::new(), |o| debug_strings(o));
+            if let Some(index) = first_difference(recorded, &found) {
+                return Err(RecordError::ObservationMismatch {
+                    player,
+                    index,
+                    recorded: recorded.get(index).cloned(),
+                    found: found.get(index).cloned(),
+                });
+            }
+        }
+        match hist.active {
+            ActivePlayer::Terminal(ref u) if u == &self.utilities => Ok(hist),
+            ActivePlayer::Terminal(ref u) => Err(RecordError::UtilityMismatch {
+                recorded: self.utilities.clone(),
+                found: u.clone(),
+            }),
+            _ => Err(RecordError::NotTerminal),
+        }
+    }
+}
+
+/// Writes trajectories of one game as JSON lines.
+pub struct TrajectoryWriter<W: Write> {
+    writer: W,
+    spec: String,
+}
+
+impl<W: Write> TrajectoryWriter<W> {
+    /// Writes trajectories of the game identified by `spec`.
+    pub fn new(writer: W, spec: &str) -> Self {
+        TrajectoryWriter {
+            writer,
+            spec: spec.to_string(),
+        }
+    }
+
+    /// Fails with `InvalidInput` on a record of another game spec and with `InvalidData`
+    /// on non-finite utilities.
+    pub fn write_record(&mut self, record: &TrajectoryRecord) -> io::Result<()> {
+        if record.game != self.spec {
+            let e = RecordError::GameMismatch {
+                recorded: record.game.clone(),
+                found: self.spec.clone(),
+            };
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e.to_string()));
+        }
+        let line = record
+            .to_json()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        writeln!(self.writer, "{}", line)
+    }
+
+    pub fn write_history<G: Game>(&mut self, hist: &HistoryInfo<G>) -> io::Result<()> {
+        self.write_record(&TrajectoryRecord::from_history(&self.spec, hist))
+    }
+
+    pub fn write_trajectory<G: Game>(&mut self, trajectory: &Trajectory<G>) -> io::Result<()> {
+        self.write_history(trajectory.last())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Read all records of a trajectory log, skipping empty lines.
+pub fn read_records<R: BufRead>(reader: R) -> Result<Vec<TrajectoryRecord>, RecordError> {
+    read_json_lines(reader, TrajectoryRecord::from_json)
+}
+
+/// Decode every non-empty line of a JSON lines file with `decode`.
+pub(crate) fn read_json_lines<R: BufRead, T, F: FnMut(&str) -> Result<T, String>>(
+    reader: R,
+    mut decode: F,
+) -> Result<Vec<T>, RecordError> {
+    let mut res = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        res.push(decode(&line).map_err(|message| RecordError::Parse {
+            line: i + 1,
+            message,
+        })?);
+    }
+    Ok(res)
+}
+
+fn debug_strings<T: fmt::Debug>(items: &[T]) -> Vec<String> {
+    items.iter().map(|v| format!("{:?}", v)).collect()
+}
+
+/// Index of the first difference of the sequences (including one ending early).
+fn first_difference(a: &[String], b: &[String]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        None if a.len() == b.len() => None,
+        None => Some(a.len().min(b.len())),
+        i => i,
+    }
+}
+
+/// Write the numbers as a JSON array (`Debug` of finite `f64` is valid JSON).
+pub(crate) fn write_json_nums<T: fmt::Debug>(s: &mut String, items: &[T]) {
+    s.push('[');
+    for (i, v) in items.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        write!(s, "{:?}", v).unwrap();
+    }
+    s.push(']');
+}
+
+pub(crate) fn write_json_strs(s: &mut String, items: &[String]) {
+    s.push('[');
+    for (i, v) in items.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        write_json_str(s, v);
+    }
+    s.push(']');
+}
+
+pub(crate) fn write_json_str(s: &mut String, v: &str) {
+    s.push('"');
+    for c in v.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+/// Minimal parser for the subset of JSON used by the log format.
+pub(crate) struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse `line` as one JSON object, calling `field` to parse the value of every key.
+    pub(crate) fn object<F>(line: &'a str, mut field: F) -> Result<(), String>
+    where
+        F: FnMut(&mut Self, &str) -> Result<(), String>,
+    {
+        let mut p = Parser {
+            s: line.as_bytes(),
+            pos: 0,
+        };
+        p.expect(b'{')?;
+        loop {
+            let key = p.string()?;
+            p.expect(b':')?;
+            field(&mut p, &key)?;
+            if !p.comma_or(b'}')? {
+                break;
+            }
+        }
+        p.skip_ws();
+        if p.pos != p.s.len() {
+            return Err(format!("trailing characters at {}", p.pos));
+        }
+        Ok(())
+    }
+
+    fn skip_ws(&mut self) {
+        while self.pos < self.s.len() && (self.s[self.pos] as char).is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.s.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected {:?} at {}", c as char, self.pos))
+        }
+    }
+
+    /// Consume either a comma (returns true) or the given closing bracket (returns false).
+    fn comma_or(&mut self, close: u8) -> Result<bool, String> {
+        self.skip_ws();
+        match self.s.get(self.pos) {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(&c) if c == close => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(format!(
+                "expected ',' or {:?} at {}",
+                close as char, self.pos
+            )),
+        }
+    }
+
+    pub(crate) fn array<T, F: FnMut(&mut Self) -> Result<T, String>>(
+        &mut self,
+        mut item: F,
+    ) -> Result<Vec<T>, String> {
+        self.expect(b'[')?;
+        let mut res = Vec::new();
+        self.skip_ws();
+        if self.s.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(res);
+        }
+        loop {
+            res.push(item(self)?);
+            if !self.comma_or(b']')? {
+                return Ok(res);
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<&'a str, String> {
+        self.skip_ws();
+        let start = self.pos;
+        while self.pos < self.s.len() && b"+-.0123456789eE".contains(&self.s[self.pos]) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!("expected a number at {}", start));
+        }
+        Ok(std::str::from_utf8(&self.s[start..self.pos]).unwrap())
+    }
+
+    pub(crate) fn parse<T: std::str::FromStr>(&mut self, what: &str) -> Result<T, String>
+    where
+        T::Err: fmt::Display,
+    {
+        self.number()?
+            .parse()
+            .map_err(|e| format!("invalid {}: {}", what, e))
+    }
+
+    /// The four hex digits at `pos`.
+    fn hex4(&self, pos: usize) -> Option<u32> {
+        self.s
+            .get(pos..pos + 4)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u32::from_str_radix(h, 16).ok())
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut buf = Vec::new();
+        loop {
+            match self.s.get(self.pos) {
+                None => return Err("unterminated string".into()),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    let c = match self.s.get(self.pos + 1) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let err = format!("invalid escape at {}", self.pos);
+                            let mut code = self.hex4(self.pos + 2).ok_or_else(|| err.clone())?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // A surrogate pair encodes a character outside the BMP
+                                let low = Some(self.pos + 6)
+                                    .filter(|&i| self.s.get(i..i + 2) == Some(&b"\\u"[..]))
+                                    .and_then(|i| self.hex4(i + 2))
+                                    .filter(|low| (0xDC00..0xE000).contains(low))
+                                    .ok_or_else(|| err.clone())?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                self.pos += 6;
+                            }
+                            self.pos += 4;
+                            std::char::from_u32(code).ok_or(err)?
+                        }
+                        _ => return Err(format!("invalid escape at {}", self.pos)),
+                    };
+                    let mut b = [0; 4];
+                    buf.extend_from_slice(c.encode_utf8(&mut b).as_bytes());
+                    self.pos += 2;
+                }
+                Some(&c) => {
+                    buf.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(buf).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_records, RecordError, TrajectoryRecord, TrajectoryWriter};
+    use crate::{goofspiel, simulate, Goofspiel, UniformStrategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_record_replay_goofspiel() {
+        let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut w = TrajectoryWriter::new(Vec::new(), "goofspiel-4");
+        let ts: Vec<_> = (0..5).map(|_| simulate(&g, &[&u, &u], &mut rng)).collect();
+        for t in &ts {
+            w.write_trajectory(t).unwrap();
+        }
+        let data = w.into_inner();
+        let recs = read_records(&data[..]).unwrap();
+        assert_eq!(recs.len(), 5);
+        for (r, t) in recs.iter().zip(&ts) {
+            assert_eq!(r, &TrajectoryRecord::from_trajectory("goofspiel-4", t));
+            let h = r.replay(&g, "goofspiel-4").unwrap();
+            assert_eq!(h.history, t.last().history);
+        }
+        match recs[0].replay(&g, "goofspiel-5") {
+            Err(RecordError::GameMismatch { .. }) => (),
+            r => panic!("expected a game mismatch, got {:?}", r),
+        }
+        let mut bad = recs[0].clone();
+        bad.actions[2] = "5".into();
+        match bad.replay(&g, "goofspiel-4") {
+            Err(RecordError::ActionMismatch { step: 2, .. }) => (),
+            r => panic!("expected an action mismatch, got {:?}", r),
+        }
+        let mut bad = recs[0].clone();
+        bad.observations[1][0] = "Obs(Card(9))".into();
+        match bad.replay(&g, "goofspiel-4") {
+            Err(RecordError::ObservationMismatch {
+                player: 1,
+                index: 0,
+                ..
+            }) => (),
+            r => panic!("expected an observation mismatch, got {:?}", r),
+        }
+        bad.observations[1].pop();
+        assert!(bad.replay(&g, "goofspiel-4").is_err());
+        let mut bad = recs[0].clone();
+        bad.utilities[0] += 1.0;
+        assert!(bad.replay(&g, "goofspiel-4").is_err());
+        bad.utilities[0] = f64::NAN;
+        match bad.to_json() {
+            Err(RecordError::NonFiniteUtility { player: 0 }) => (),
+            r => panic!("expected a non-finite utility error, got {:?}", r),
+        }
+        let mut w = TrajectoryWriter::new(Vec::new(), "goofspiel-4");
+        assert!(w.write_record(&bad).is_err());
+        let mut w = TrajectoryWriter::new(Vec::new(), "goofspiel-5");
+        assert!(w.write_record(&recs[0]).is_err());
+        assert!(
+            TrajectoryRecord::from_json("{\"game\":\"a\\\"b\"}")
+                .unwrap()
+                .game
+                == "a\"b"
+        );
+        let rec = TrajectoryRecord::from_json("{\"game\":\"\\u00e9\\ud83d\\ude00\"}").unwrap();
+        assert_eq!(rec.game, "\u{e9}\u{1f600}");
+        assert!(TrajectoryRecord::from_json("{\"game\":\"\\ud83d\"}").is_err());
+    }
+}
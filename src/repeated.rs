@@ -0,0 +1,390 @@
+use crate::{ActivePlayer, Categorical, Game, HistoryInfo, Observation, Utility};
+use std::fmt::Debug;
+
+/// What the players learn about the player actions of a finished stage (chance outcomes
+/// are only revealed by the stage observations).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Monitoring {
+    /// Only the observations of the stage game itself.
+    None,
+    /// Everyone observes all actions of the stage.
+    Perfect,
+    /// Every player privately observes the actions of the stage, where every action of
+    /// another player is independently replaced by a uniformly random available action
+    /// with the given probability.
+    Noisy(f64),
+}
+
+/// How stage payoffs are combined into the final payoff.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    Sum,
+    Average,
+    /// Payoff of round `t` (from 0) is multiplied by `factor^t`.
+    Discounted(f64),
+}
+
+/// Transition between the stage games of a stochastic game.
+pub trait Transition<G: Game>: Debug + Clone {
+    /// Distribution of the next stage index after stage `stage` ended with `outcome`.
+    fn next_stage(&self, stage: usize, outcome: &HistoryInfo<G>) -> Categorical<usize>;
+}
+
+/// Always stay in the same stage game, giving a repeated game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stay;
+
+impl<G: Game> Transition<G> for Stay {
+    fn next_stage(&self, stage: usize, _outcome: &HistoryInfo<G>) -> Categorical<usize> {
+        Categorical::new(vec![1.0], vec![stage])
+    }
+}
+
+/// A finite-horizon stochastic (Markov) game over stage games of type `G`.
+///
+/// In every round, the current stage game is played to the end, the players observe
+/// the outcome according to `monitoring`, and the next stage is chosen by `transition`
+/// (a public chance node unless the transition is deterministic).
+/// The game ends after `horizon` rounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StochasticGame<G: Game, T: Transition<G>> {
+    pub stages: Vec<G>,
+    pub transition: T,
+    pub horizon: usize,
+    pub monitoring: Monitoring,
+    pub aggregation: Aggregation,
+}
+
+/// A finitely repeated game.
+pub type RepeatedGame<G> = StochasticGame<G, Stay>;
+
+impl<G: Game> StochasticGame<G, Stay> {
+    /// Repeat `stage` for `horizon` rounds with perfect monitoring and summed payoffs.
+    pub fn repeated(stage: G, horizon: usize) -> Self {
+        Self::new(vec![stage], Stay, horizon)
+    }
+}
+
+impl<G: Game, T: Transition<G>> StochasticGame<G, T> {
+    /// Stochastic game starting in `stages[0]`, with perfect monitoring and summed payoffs.
+    pub fn new(stages: Vec<G>, transition: T, horizon: usize) -> Self {
+        assert!(!stages.is_empty() && horizon > 0);
+        let players = stages[0].players();
+        assert!(stages.iter().all(|s| s.players() == players));
+        StochasticGame {
+            stages,
+            transition,
+            horizon,
+            monitoring: Monitoring::Perfect,
+            aggregation: Aggregation::Sum,
+        }
+    }
+
+    pub fn with_monitoring(mut self, monitoring: Monitoring) -> Self {
+        self.monitoring = monitoring;
+        self
+    }
+
+    pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    fn wrap_active(active: &ActivePlayer<G>) -> ActivePlayer<Self> {
+        match active {
+            ActivePlayer::Player(p, ref actions) => ActivePlayer::Player(
+                *p,
+                actions
+                    .iter()
+                    .map(|a| RoundAction::Stage(a.clone()))
+                    .collect(),
+            ),
+            ActivePlayer::Chance(ref d) => ActivePlayer::Chance(Categorical::new(
+                d.probs().clone(),
+                d.items()
+                    .iter()
+                    .map(|a| RoundAction::Stage(a.clone()))
+                    .collect::<Vec<_>>(),
+            )),
+            ActivePlayer::Terminal(_) => panic!("stage game starts in a terminal state"),
+        }
+    }
+
+    /// Start the stage game with index `stage`.
+    fn start_stage(
+        &self,
+        mut state: RoundState<G>,
+        stage: usize,
+    ) -> (RoundState<G>, ActivePlayer<Self>) {
+        let hist = self.stages[stage].start();
+        let active = Self::wrap_active(&hist.active);
+        state.stage = stage;
+        state.hist = hist;
+        state.steps.clear();
+        state.phase = Phase::Play;
+        (state, active)
+    }
+
+    /// Continue after the monitoring of a finished stage.
+    fn next_round(&self, mut state: RoundState<G>) -> (RoundState<G>, ActivePlayer<Self>) {
+        state.round += 1;
+        if state.round >= self.horizon {
+            let mut u = state.payoffs.clone();
+            if self.aggregation == Aggregation::Average {
+                u.iter_mut().for_each(|v| *v /= self.horizon as f64);
+            }
+            return (state, ActivePlayer::Terminal(u));
+        }
+        let next = self.transition.next_stage(state.stage, &state.hist);
+        if next.items().len() == 1 {
+            let s = next.items()[0];
+            self.start_stage(state, s)
+        } else {
+            state.phase = Phase::Transition;
+            let active = ActivePlayer::Chance(Categorical::new(
+                next.probs().clone(),
+                next.items()
+                    .iter()
+                    .map(|&s| RoundAction::Next(s))
+                    .collect::<Vec<_>>(),
+            ));
+            (state, active)
+        }
+    }
+
+    /// The monitoring chance node of `player` (or the public one for `None`).
+    fn signal(&self, state: &RoundState<G>, player: Option<usize>) -> ActivePlayer<Self> {
+        let eps = match (self.monitoring, player) {
+            (Monitoring::Noisy(eps), Some(_)) => eps,
+            _ => 0.0,
+        };
+        let mut signals: Vec<(f64, Vec<G::Action>)> = vec![(1.0, Vec::new())];
+        for (step, &ai) in state.steps.iter().zip(&state.hist.history_indices) {
+            let (ref p, ref actions) = *step;
+            if p.is_none() {
+                continue;
+            }
+            let k = actions.len();
+            let noisy = eps > 0.0 && *p != player;
+            let mut next = Vec::new();
+            for (pr, s) in &signals {
+                for (i, a) in actions.iter().enumerate() {
+                    let pa = match (noisy, i == ai as usize) {
+                        (false, true) => 1.0,
+                        (false, false) => continue,
+                        (true, true) => 1.0 - eps + eps / k as f64,
+                        (true, false) => eps / k as f64,
+                    };
+                    let mut s2 = s.clone();
+                    s2.push(a.clone());
+                    next.push((pr * pa, s2));
+                }
+            }
+            signals = next;
+        }
+        let (probs, items): (Vec<_>, Vec<_>) = signals
+            .into_iter()
+            .map(|(p, s)| (p, RoundAction::Signal(s)))
+            .unzip();
+        ActivePlayer::Chance(Categorical::new(probs, items))
+    }
+}
+
+/// Actions of a stochastic game: stage game actions, monitoring signals and stage transitions.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum RoundAction<A> {
+    Stage(A),
+    Signal(Vec<A>),
+    Next(usize),
+}
+
+/// Observations of a stochastic game.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum RoundObservation<A, O> {
+    /// All observations of one step of the stage game.
+    Stage(Vec<O>),
+    /// Monitoring of the player actions of the last stage.
+    Outcome(Vec<A>),
+    /// The next stage game.
+    Next(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Phase {
+    Play,
+    /// Monitoring chance node of the given player, `None` for the public one.
+    Signal(Option<usize>),
+    Transition,
+}
+
+#[derive(Clone, Debug)]
+pub struct RoundState<G: Game> {
+    pub round: usize,
+    pub stage: usize,
+    /// History of the current (or just finished) stage game.
+    pub hist: HistoryInfo<G>,
+    /// Active player and available actions in every step of the current stage.
+    pub steps: Vec<(Option<usize>, Vec<G::Action>)>,
+    /// Aggregated payoffs of the finished rounds.
+    pub payoffs: Vec<Utility>,
+    pub phase: Phase,
+}
+
+impl<G: Game, T: Transition<G>> Game for StochasticGame<G, T> {
+    type State = RoundState<G>;
+    type Observation = RoundObservation<G::Action, G::Observation>;
+    type Action = RoundAction<G::Action>;
+
+    fn players(&self) -> usize {
+        self.stages[0].players()
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let state = RoundState {
+            round: 0,
+            stage: 0,
+            hist: self.stages[0].start(),
+            steps: Vec::new(),
+            payoffs: vec![0.0; self.players()],
+            phase: Phase::Play,
+        };
+        self.start_stage(state, 0)
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let n = self.players();
+        let mut state = hist.state.clone();
+        let mut obs = vec![None; n + 1];
+        let active = match (&hist.state.phase, action) {
+            (Phase::Play, RoundAction::Stage(a)) => {
+                let game = &self.stages[state.stage];
+                let h = &hist.state.hist;
+                state
+                    .steps
+                    .push((h.active.player(), h.active.actions().to_vec()));
+                state.hist = game.play_value(h, a);
+                for (o, os) in obs.iter_mut().zip(state.hist.observations_since(h)) {
+                    let so: Vec<_> = os
+                        .iter()
+                        .filter_map(|o| match o {
+                            Observation::Obs(o) => Some(o.clone()),
+                            Observation::Own(_) => None,
+                        })
+                        .collect();
+                    if !so.is_empty() {
+                        *o = Some(RoundObservation::Stage(so));
+                    }
+                }
+                if let ActivePlayer::Terminal(ref u) = state.hist.active {
+                    let w = match self.aggregation {
+                        Aggregation::Discounted(f) => f.powi(state.round as i32),
+                        _ => 1.0,
+                    };
+                    state
+                        .payoffs
+                        .iter_mut()
+                        .zip(u)
+                        .for_each(|(p, v)| *p += w * v);
+                    // No monitoring needed after the last round
+                    if state.round + 1 >= self.horizon || self.monitoring == Monitoring::None {
+                        let (s, a) = self.next_round(state);
+                        return (s, a, obs);
+                    }
+                    let player = match self.monitoring {
+                        Monitoring::Noisy(_) => Some(0),
+                        _ => None,
+                    };
+                    state.phase = Phase::Signal(player);
+                    self.signal(&state, player)
+                } else {
+                    Self::wrap_active(&state.hist.active)
+                }
+            }
+            (Phase::Signal(None), RoundAction::Signal(s)) => {
+                obs = vec![Some(RoundObservation::Outcome(s.clone())); n + 1];
+                let (s, a) = self.next_round(state);
+                return (s, a, obs);
+            }
+            (Phase::Signal(Some(p)), RoundAction::Signal(s)) => {
+                obs[*p] = Some(RoundObservation::Outcome(s.clone()));
+                if p + 1 < n {
+                    state.phase = Phase::Signal(Some(p + 1));
+                    self.signal(&state, Some(p + 1))
+                } else {
+                    let (s, a) = self.next_round(state);
+                    return (s, a, obs);
+                }
+            }
+            (Phase::Transition, RoundAction::Next(s)) => {
+                obs = vec![Some(RoundObservation::Next(*s)); n + 1];
+                let (st, a) = self.start_stage(state, *s);
+                return (st, a, obs);
+            }
+            (p, a) => panic!("invalid action {:?} in phase {:?}", a, p),
+        };
+        (state, active, obs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Aggregation, Monitoring, Phase, RoundAction, RoundObservation, StochasticGame};
+    use crate::Observation::*;
+    use crate::{
+        check_perfect_recall, goofspiel, ActivePlayer, Game, Goofspiel, MatrixGame, Sequentialized,
+    };
+
+    #[test]
+    fn test_repeated_prisoners_dilemma() {
+        // Actions: 0 = cooperate, 1 = defect
+        let pd = Sequentialized::new(MatrixGame::bimatrix(
+            &[vec![3.0, 0.0], vec![5.0, 1.0]],
+            &[vec![3.0, 5.0], vec![0.0, 1.0]],
+        ));
+        let g =
+            StochasticGame::repeated(pd.clone(), 2).with_aggregation(Aggregation::Discounted(0.5));
+        // Round 1: (C, D), public signal, round 2: (D, D)
+        let mut h = g.start();
+        for ai in &[0, 1, 0, 1, 1] {
+            h = g.play_owned(h, *ai);
+        }
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![0.5, 5.5]));
+        assert_eq!(h.observations[0].len(), 5);
+        assert!(check_perfect_recall(&g).is_ok());
+
+        let g = StochasticGame::repeated(pd, 2).with_monitoring(Monitoring::Noisy(0.2));
+        let h = g.play_owned(g.play_owned(g.start(), 0), 1);
+        if let ActivePlayer::Chance(ref d) = h.active {
+            assert_eq!(d.items().len(), 2);
+            assert!((d.probs()[1] - 0.9).abs() < 1e-9);
+        } else {
+            panic!("expected the monitoring chance node");
+        }
+        assert!(check_perfect_recall(&g).is_ok());
+    }
+
+    #[test]
+    fn test_monitoring_hides_chance() {
+        let g = StochasticGame::repeated(Goofspiel::new(2, goofspiel::Scoring::ZeroSum), 2);
+        // Point card 1, both players bid 1, then the last point card and bids of round 1
+        let mut h = g.play_owned(g.start(), 0);
+        assert_eq!(
+            h.observations[0],
+            vec![Obs(RoundObservation::Stage(vec![goofspiel::Info::Card(1)]))]
+        );
+        while h.state.phase == Phase::Play {
+            h = g.play_owned(h, 0);
+        }
+        assert_eq!(h.state.phase, Phase::Signal(None));
+        // Only the four bids are revealed, not the point cards
+        assert_eq!(h.active.actions(), &[RoundAction::Signal(vec![1, 1, 2, 2])]);
+    }
+}
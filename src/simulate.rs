@@ -0,0 +1,146 @@
+use crate::{ActionIndex, ActivePlayer, Game, HistoryInfo, Strategy, Utility};
+use rand::Rng;
+
+/// A recorded play of a game.
+#[derive(Clone, Debug)]
+pub struct Trajectory<G: Game> {
+    /// All visited histories, from the initial one to the terminal one.
+    pub histories: Vec<HistoryInfo<G>>,
+    /// Action probabilities used in each non-terminal history
+    /// (the chance distribution in chance nodes).
+    pub policies: Vec<Vec<f64>>,
+    /// Terminal payoffs.
+    pub utilities: Vec<Utility>,
+}
+
+impl<G: Game> Trajectory<G> {
+    /// The terminal history.
+    pub fn last(&self) -> &HistoryInfo<G> {
+        self.histories.last().expect("empty trajectory")
+    }
+
+    /// Sampled action indices.
+    pub fn actions(&self) -> &[ActionIndex] {
+        &self.last().history_indices
+    }
+}
+
+/// Play one game with the given strategies (one per player), recording the whole trajectory.
+pub fn simulate<G: Game, R: Rng>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    rng: &mut R,
+) -> Trajectory<G> {
+    assert_eq!(strategies.len(), game.players());
+    let mut histories = vec![game.start()];
+    let mut policies = Vec::new();
+    loop {
+        let hist = histories.last().unwrap();
+        let (ai, probs) = match hist.active {
+            ActivePlayer::Terminal(ref payoffs) => {
+                let utilities = payoffs.clone();
+                return Trajectory {
+                    histories,
+                    policies,
+                    utilities,
+                };
+            }
+            ActivePlayer::Chance(ref dist) => (dist.sample_idx_rng(rng), dist.probs().clone()),
+            ActivePlayer::Player(p, _) => {
+                let p = p as usize;
+                let policy = strategies[p].policy(&hist.active, &hist.observations[p]);
+                let ai = *policy.sample_ref_rng(rng) as usize;
+                let mut probs = vec![0.0; hist.active.actions().len()];
+                for (a, pr) in policy.items().iter().zip(policy.probs()) {
+                    probs[*a as usize] += pr;
+                }
+                (ai, probs)
+            }
+        };
+        let next = game.play(hist, ai);
+        policies.push(probs);
+        histories.push(next);
+    }
+}
+
+/// Play `count` games with the given strategies, recording all trajectories.
+pub fn simulate_many<G: Game, R: Rng>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    count: usize,
+    rng: &mut R,
+) -> Vec<Trajectory<G>> {
+    (0..count)
+        .map(|_| simulate(game, strategies, rng))
+        .collect()
+}
+
+/// Play one game with the given strategies, returning only the payoffs.
+/// Cheaper than `simulate` as no intermediate histories are kept.
+pub fn playout<G: Game, R: Rng>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    rng: &mut R,
+) -> Vec<Utility> {
+    let mut hist = game.start();
+    loop {
+        let ai = match hist.active {
+            ActivePlayer::Terminal(ref payoffs) => return payoffs.clone(),
+            ActivePlayer::Chance(ref dist) => dist.sample_idx_rng(rng),
+            ActivePlayer::Player(p, _) => {
+                let p = p as usize;
+                let policy = strategies[p].policy(&hist.active, &hist.observations[p]);
+                *policy.sample_ref_rng(rng) as usize
+            }
+        };
+        hist = game.play_owned(hist, ai);
+    }
+}
+
+/// Mean payoffs of `count` (at least one) playouts.
+pub fn mean_utility<G: Game, R: Rng>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    count: usize,
+    rng: &mut R,
+) -> Vec<Utility> {
+    assert!(count > 0, "mean utility of no playouts");
+    let mut sum = vec![0.0; game.players()];
+    for _ in 0..count {
+        let u = playout(game, strategies, rng);
+        sum.iter_mut().zip(u).for_each(|(s, v)| *s += v);
+    }
+    sum.iter().map(|s| s / count as f64).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{mean_utility, simulate, simulate_many};
+    use crate::{goofspiel, ActivePlayer, Goofspiel, UniformStrategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_simulate_goofspiel() {
+        let g = Goofspiel::new(4, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        let mut rng = SmallRng::seed_from_u64(1);
+        let t = simulate(&g, &[&u, &u], &mut rng);
+        assert_eq!(t.histories.len(), 13);
+        assert_eq!(t.policies.len(), 12);
+        assert_eq!(t.actions().len(), 12);
+        assert_eq!(t.policies[0], vec![0.25; 4]);
+        assert_eq!(t.policies[11], vec![1.0]);
+        assert_eq!(t.last().active, ActivePlayer::Terminal(t.utilities.clone()));
+        assert_eq!(simulate_many(&g, &[&u, &u], 10, &mut rng).len(), 10);
+        let m = mean_utility(&g, &[&u, &u], 2000, &mut rng);
+        assert!(m[0].abs() < 0.3 && (m[0] + m[1]).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "no playouts")]
+    fn test_mean_of_nothing() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        mean_utility(&g, &[&u, &u], 0, &mut SmallRng::seed_from_u64(1));
+    }
+}
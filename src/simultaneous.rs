@@ -0,0 +1,290 @@
+use crate::{ActivePlayer, Categorical, Game, HistoryInfo, Utility};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A game played in stages where all players act simultaneously.
+///
+/// Use `Sequentialized` to turn it into a `Game`.
+pub trait SimultaneousGame: Debug + Clone {
+    type State: Clone + Debug;
+    type Observation: Clone + Debug + Hash + PartialEq + Eq;
+    type Action: Clone + Debug + Hash + PartialEq + Eq;
+
+    fn players(&self) -> usize;
+
+    fn initial_state(&self) -> (Self::State, Stage<Self>);
+
+    /// Apply the joint action of a player stage (one action per player), or the single
+    /// chance action of a chance stage. Returns the new state, the next stage and one optional
+    /// observation per player plus one for the public observer.
+    fn update_state(
+        &self,
+        state: &Self::State,
+        joint: &[Self::Action],
+    ) -> (Self::State, Stage<Self>, Vec<Option<Self::Observation>>);
+}
+
+/// The next stage of a simultaneous game.
+#[derive(Clone, Debug)]
+pub enum Stage<G: SimultaneousGame> {
+    /// Available actions of every player.
+    Players(Vec<Vec<G::Action>>),
+    Chance(Categorical<G::Action>),
+    Terminal(Vec<Utility>),
+}
+
+/// A simultaneous game lowered into a sequential `Game`.
+///
+/// In every stage the players choose their actions in order 0, 1, ... without observing the
+/// actions chosen before them in that stage. The stage observations are emitted after
+/// the last player's action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sequentialized<G: SimultaneousGame> {
+    pub game: G,
+}
+
+#[derive(Clone, Debug)]
+pub struct SequentializedState<G: SimultaneousGame> {
+    pub state: G::State,
+    pub stage: Stage<G>,
+    /// Actions already chosen in the current stage.
+    pub pending: Vec<G::Action>,
+}
+
+impl<G: SimultaneousGame> Sequentialized<G> {
+    pub fn new(game: G) -> Self {
+        Sequentialized { game }
+    }
+
+    fn enter(
+        &self,
+        state: G::State,
+        stage: Stage<G>,
+    ) -> (SequentializedState<G>, ActivePlayer<Self>) {
+        let active = match stage {
+            Stage::Players(ref actions) => {
+                assert_eq!(
+                    actions.len(),
+                    self.game.players(),
+                    "player stage needs one action set per player"
+                );
+                assert!(
+                    actions.iter().all(|a| !a.is_empty()),
+                    "player stage with no actions for a player"
+                );
+                ActivePlayer::Player(0, actions[0].clone())
+            }
+            Stage::Chance(ref dist) => ActivePlayer::Chance(dist.clone()),
+            Stage::Terminal(ref u) => ActivePlayer::Terminal(u.clone()),
+        };
+        let state = SequentializedState {
+            state,
+            stage,
+            pending: Vec::new(),
+        };
+        (state, active)
+    }
+}
+
+impl<G: SimultaneousGame> Game for Sequentialized<G> {
+    type State = SequentializedState<G>;
+    type Observation = G::Observation;
+    type Action = G::Action;
+
+    fn players(&self) -> usize {
+        self.game.players()
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let (state, stage) = self.game.initial_state();
+        self.enter(state, stage)
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let s = &hist.state;
+        let (state, stage, obs) = match s.stage {
+            Stage::Players(ref actions) => {
+                if s.pending.len() + 1 < actions.len() {
+                    let mut state = s.clone();
+                    state.pending.push(action.clone());
+                    let p = state.pending.len();
+                    let active = ActivePlayer::Player(p as u32, actions[p].clone());
+                    return (state, active, vec![None; self.players() + 1]);
+                }
+                let mut joint = s.pending.clone();
+                joint.push(action.clone());
+                self.game.update_state(&s.state, &joint)
+            }
+            Stage::Chance(_) => self
+                .game
+                .update_state(&s.state, std::slice::from_ref(action)),
+            Stage::Terminal(_) => panic!("playing in terminal state {:?}", s),
+        };
+        let (state, active) = self.enter(state, stage);
+        (state, active, obs)
+    }
+}
+
+/// A one-shot normal-form game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixGame {
+    /// Number of actions of every player.
+    pub actions: Vec<usize>,
+    /// Utilities of all players for every joint action, indexed in row-major order
+    /// (the last player's action changes fastest).
+    pub payoffs: Vec<Vec<Utility>>,
+}
+
+impl MatrixGame {
+    pub fn new(actions: Vec<usize>, payoffs: Vec<Vec<Utility>>) -> Self {
+        assert!(
+            actions.iter().all(|&n| n > 0),
+            "every player needs at least one action"
+        );
+        assert_eq!(actions.iter().product::<usize>(), payoffs.len());
+        assert!(payoffs.iter().all(|u| u.len() == actions.len()));
+        MatrixGame { actions, payoffs }
+    }
+
+    /// Two-player game from the row and column player's payoff matrices.
+    pub fn bimatrix(rows: &[Vec<Utility>], cols: &[Vec<Utility>]) -> Self {
+        assert!(
+            !rows.is_empty() && !rows[0].is_empty(),
+            "payoff matrices must not be empty"
+        );
+        assert_eq!(rows.len(), cols.len(), "payoff matrices differ in size");
+        let n = rows[0].len();
+        assert!(
+            rows.iter().chain(cols).all(|r| r.len() == n),
+            "payoff matrices must be rectangular and of the same size"
+        );
+        let payoffs = rows
+            .iter()
+            .zip(cols)
+            .flat_map(|(r, c)| r.iter().zip(c).map(|(&a, &b)| vec![a, b]))
+            .collect();
+        Self::new(vec![rows.len(), n], payoffs)
+    }
+
+    /// Two-player zero-sum game from the row player's payoff matrix.
+    pub fn zero_sum(rows: &[Vec<Utility>]) -> Self {
+        let cols: Vec<Vec<_>> = rows
+            .iter()
+            .map(|r| r.iter().map(|v| -v).collect())
+            .collect();
+        Self::bimatrix(rows, &cols)
+    }
+
+    /// Utilities of the given joint action.
+    pub fn utilities(&self, joint: &[u32]) -> &[Utility] {
+        let idx = joint
+            .iter()
+            .zip(&self.actions)
+            .fold(0, |i, (&a, &n)| i * n + a as usize);
+        &self.payoffs[idx]
+    }
+}
+
+impl SimultaneousGame for MatrixGame {
+    type State = ();
+    /// The joint action, revealed to everyone at the end.
+    type Observation = Vec<u32>;
+    type Action = u32;
+
+    fn players(&self) -> usize {
+        self.actions.len()
+    }
+
+    fn initial_state(&self) -> (Self::State, Stage<Self>) {
+        let actions = self
+            .actions
+            .iter()
+            .map(|&n| (0..n as u32).collect())
+            .collect();
+        ((), Stage::Players(actions))
+    }
+
+    fn update_state(
+        &self,
+        _state: &Self::State,
+        joint: &[Self::Action],
+    ) -> (Self::State, Stage<Self>, Vec<Option<Self::Observation>>) {
+        let u = self.utilities(joint).to_vec();
+        (
+            (),
+            Stage::Terminal(u),
+            vec![Some(joint.to_vec()); self.players() + 1],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MatrixGame, Sequentialized, SimultaneousGame, Stage};
+    use crate::Observation::*;
+    use crate::{check_perfect_recall, ActivePlayer, Game, OuterMCCFR, Strategy};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_matching_pennies() {
+        let g = Sequentialized::new(MatrixGame::zero_sum(&[vec![1.0, -1.0], vec![-1.0, 1.0]]));
+        let h = g.play_owned(g.start(), 1);
+        assert!(h.observations[1].is_empty());
+        let h = g.play_owned(h, 0);
+        assert_eq!(h.active, ActivePlayer::Terminal(vec![-1.0, 1.0]));
+        assert_eq!(h.observations[0], vec![Own(1), Obs(vec![1, 0])]);
+        assert!(check_perfect_recall(&g).is_ok());
+
+        let mut mc = OuterMCCFR::new(g.clone());
+        mc.compute_rng(5000, 0.6, &mut SmallRng::seed_from_u64(1));
+        let s = g.start();
+        let pol = mc.policy(&s.active, &s.observations[0]);
+        assert!((pol.probs()[0] - 0.5).abs() < 0.1);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be empty")]
+    fn test_empty_matrix() {
+        MatrixGame::zero_sum(&[]);
+    }
+
+    /// Two players, but the stage lists actions of only one.
+    #[derive(Clone, Debug)]
+    struct Lopsided;
+
+    impl SimultaneousGame for Lopsided {
+        type State = ();
+        type Observation = ();
+        type Action = u32;
+
+        fn players(&self) -> usize {
+            2
+        }
+
+        fn initial_state(&self) -> (Self::State, Stage<Self>) {
+            ((), Stage::Players(vec![vec![0, 1]]))
+        }
+
+        fn update_state(
+            &self,
+            _state: &Self::State,
+            _joint: &[Self::Action],
+        ) -> (Self::State, Stage<Self>, Vec<Option<()>>) {
+            ((), Stage::Terminal(vec![0.0, 0.0]), vec![None; 3])
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "one action set per player")]
+    fn test_stage_players() {
+        Sequentialized::new(Lopsided).start();
+    }
+}
@@ -0,0 +1,292 @@
+use crate::record::{read_json_lines, write_json_nums, write_json_str, Parser};
+use crate::{ActionIndex, ActivePlayer, Budget, Categorical, Game, PlayerObservation};
+use crate::{RecordError, SolverObserver, SolverStats, Strategy};
+use rand::RngCore;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// Saved progress of a solver, restored with `Solver::restore`.
+///
+/// Snapshots are in-memory copies, e.g. to roll back or branch a run. Use `TableSnapshot`
+/// to store the tables to disk.
+#[derive(Clone, Debug)]
+pub struct SolverSnapshot<T> {
+    pub iterations: usize,
+    pub nodes_traversed: usize,
+    pub elapsed: Duration,
+    /// Solver-specific regret and strategy tables.
+    pub tables: T,
+}
+
+/// Cumulative regrets and average strategy weights of one information set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableEntry {
+    pub regrets: Vec<f64>,
+    pub strategy_sum: Vec<f64>,
+}
+
+/// The regret and strategy tables of a solver in a serializable form.
+///
+/// Information sets are keyed by the player and the `Debug` representation of the player's
+/// observation sequence, so the tables do not depend on the representation of the game
+/// types. A snapshot read back plays the saved average strategy in any game with the same
+/// observations (uniform in unknown information sets); it can not be restored into a solver.
+///
+/// `write` and `read` use JSON lines: a header line followed by one line per information set,
+///
+/// ```text
+/// {"iterations":..,"nodes_traversed":..}
+/// {"player":0,"infoset":"[Obs(Card(2))]","regrets":[..],"strategy_sum":[..]}
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableSnapshot {
+    pub iterations: usize,
+    pub nodes_traversed: usize,
+    pub infosets: BTreeMap<(usize, String), TableEntry>,
+}
+
+impl TableSnapshot {
+    pub fn new(iterations: usize, nodes_traversed: usize) -> Self {
+        TableSnapshot {
+            iterations,
+            nodes_traversed,
+            infosets: BTreeMap::new(),
+        }
+    }
+
+    /// Add the tables of the information set of `player` with observations `obs`.
+    pub fn insert<O: std::fmt::Debug>(&mut self, player: usize, obs: &[O], entry: TableEntry) {
+        self.infosets.insert((player, format!("{:?}", obs)), entry);
+    }
+
+    /// Write as JSON lines. Fails with `InvalidData` on non-finite values.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{{\"iterations\":{},\"nodes_traversed\":{}}}",
+            self.iterations, self.nodes_traversed
+        )?;
+        for ((player, key), e) in &self.infosets {
+            if !e
+                .regrets
+                .iter()
+                .chain(&e.strategy_sum)
+                .all(|v| v.is_finite())
+            {
+                let msg = format!("non-finite table value in {}", key);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            let mut s = format!("{{\"player\":{},\"infoset\":", player);
+            write_json_str(&mut s, key);
+            s.push_str(",\"regrets\":");
+            write_json_nums(&mut s, &e.regrets);
+            s.push_str(",\"strategy_sum\":");
+            write_json_nums(&mut s, &e.strategy_sum);
+            s.push('}');
+            writeln!(writer, "{}", s)?;
+        }
+        Ok(())
+    }
+
+    /// Read the JSON lines written by `write`.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, RecordError> {
+        let mut snapshot = None;
+        read_json_lines(reader, |line| {
+            let mut header = TableSnapshot::new(0, 0);
+            let (mut player, mut key) = (None, None);
+            let mut entry = TableEntry {
+                regrets: Vec::new(),
+                strategy_sum: Vec::new(),
+            };
+            Parser::object(line, |p, k| {
+                match k {
+                    "iterations" => header.iterations = p.parse("iteration count")?,
+                    "nodes_traversed" => header.nodes_traversed = p.parse("node count")?,
+                    "player" => player = Some(p.parse("player")?),
+                    "infoset" => key = Some(p.string()?),
+                    "regrets" => entry.regrets = p.array(|p| p.parse("regret"))?,
+                    "strategy_sum" => entry.strategy_sum = p.array(|p| p.parse("weight"))?,
+                    k => return Err(format!("unknown key {:?}", k)),
+                }
+                Ok(())
+            })?;
+            match (snapshot.as_mut(), player, key) {
+                (None, None, None) => snapshot = Some(header),
+                (Some(s), Some(player), Some(key)) => {
+                    if entry.regrets.len() != entry.strategy_sum.len() {
+                        return Err("regrets and strategy sums differ in length".into());
+                    }
+                    s.infosets.insert((player, key), entry);
+                }
+                (None, _, _) => return Err("missing header".into()),
+                _ => return Err("expected an information set".into()),
+            }
+            Ok(())
+        })?;
+        snapshot.ok_or_else(|| RecordError::Parse {
+            line: 1,
+            message: "missing header".into(),
+        })
+    }
+}
+
+/// Plays the saved average strategy, uniform in unknown information sets.
+impl<G: Game> Strategy<G> for TableSnapshot {
+    fn policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        let (p, n) = match active {
+            ActivePlayer::Player(p, ref actions) => (*p as usize, actions.len()),
+            _ => panic!("strategy requested for non-player state {:?}", active),
+        };
+        let vs = (0..n as ActionIndex).collect::<Vec<_>>();
+        match self.infosets.get(&(p, format!("{:?}", obs))) {
+            Some(e) if e.strategy_sum.len() == n && e.strategy_sum.iter().sum::<f64>() >= 1e-6 => {
+                Categorical::new_normalized(&e.strategy_sum[..], vs)
+            }
+            _ => Categorical::uniform(vs),
+        }
+    }
+}
+
+/// Common interface of the iterative equilibrium solvers.
+///
+/// The solver's `Strategy` is its average strategy. Solvers that do not sample ignore the
+/// passed RNG. The run loops (`iterate_observed`, `compute_budget` and its variants) are
+/// implemented here once for all solvers.
+pub trait Solver<G: Game>: Strategy<G> {
+    type Tables: Clone;
+
+    /// Run `iterations` iterations.
+    fn iterate(&mut self, iterations: usize, rng: &mut dyn RngCore);
+
+    /// The current (e.g. regret-matching) policy in the information set.
+    fn current_policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex>;
+
+    /// Progress so far, with the total time spent iterating.
+    fn stats(&self) -> SolverStats;
+
+    fn save(&self) -> SolverSnapshot<Self::Tables>;
+
+    /// Replace the solver progress by a snapshot saved from a solver of the same game.
+    fn restore(&mut self, snapshot: SolverSnapshot<Self::Tables>);
+
+    /// The tables in a serializable form.
+    fn table_snapshot(&self) -> TableSnapshot;
+
+    /// Run a single iteration.
+    fn step(&mut self, rng: &mut dyn RngCore) {
+        self.iterate(1, rng)
+    }
+
+    /// The average policy in the information set.
+    fn average_policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        self.policy(active, obs)
+    }
+
+    /// Run `iterations` iterations, calling `observer` after every `every` iterations and
+    /// at the end (also when `iterations` is 0).
+    fn iterate_observed(
+        &mut self,
+        iterations: usize,
+        every: usize,
+        observer: &mut dyn SolverObserver<G>,
+        rng: &mut dyn RngCore,
+    ) where
+        Self: Sized,
+    {
+        let mut done = 0;
+        loop {
+            let k = every.max(1).min(iterations - done);
+            self.iterate(k, rng);
+            done += k;
+            observer.observe(&self.stats(), self);
+            if done >= iterations {
+                return;
+            }
+        }
+    }
+
+    /// Run iterations until the budget is exhausted. Returns the number of iterations run.
+    fn compute_budget(&mut self, budget: &Budget, rng: &mut dyn RngCore) -> usize {
+        let start = Instant::now();
+        let before = self.stats().nodes_traversed;
+        let mut done = 0;
+        while !budget.exhausted(start, done, self.stats().nodes_traversed - before) {
+            self.step(rng);
+            done += 1;
+        }
+        done
+    }
+
+    /// Run iterations for (at least) the given wall-clock time.
+    fn compute_for(&mut self, time: Duration, rng: &mut dyn RngCore) -> usize {
+        self.compute_budget(&Budget::time(time), rng)
+    }
+
+    /// Run iterations until the total number of traversed nodes reaches `nodes`.
+    fn compute_until(&mut self, nodes: usize, rng: &mut dyn RngCore) -> usize {
+        let remaining = nodes.saturating_sub(self.stats().nodes_traversed);
+        self.compute_budget(&Budget::nodes(remaining), rng)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Solver, TableSnapshot};
+    use crate::{
+        exploitability, goofspiel, Budget, FlatCFR, FlatTree, Game, Goofspiel, OuterMCCFR,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
+    use std::time::Duration;
+
+    fn train<S: Solver<Goofspiel>>(solver: &mut S, g: &Goofspiel) -> f64 {
+        let mut rng = SmallRng::seed_from_u64(1);
+        solver.iterate(100, &mut rng);
+        let saved = solver.save();
+        let expl = exploitability(g, solver);
+        solver.iterate(100, &mut rng);
+        assert_eq!(solver.stats().iterations, 200);
+        assert!(solver.stats().elapsed > Duration::default());
+        solver.restore(saved);
+        assert_eq!(solver.stats().iterations, 100);
+        assert_eq!(exploitability(g, solver), expl);
+        assert_eq!(solver.compute_budget(&Budget::iterations(3), &mut rng), 3);
+        assert_eq!(solver.stats().iterations, 103);
+        let s = g.start();
+        let s = g.play(&s, 1);
+        let cur = solver.current_policy(&s.active, &s.observations[0]);
+        assert!((cur.probs().iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // The tables survive a text round trip and play the same average strategy
+        let table = solver.table_snapshot();
+        let mut buf = Vec::new();
+        table.write(&mut buf).unwrap();
+        let read = TableSnapshot::read(&buf[..]).unwrap();
+        assert_eq!(read, table);
+        assert_eq!(read.iterations, 103);
+        assert!((exploitability(g, &read) - exploitability(g, solver)).abs() < 1e-9);
+        assert!(TableSnapshot::read(&b"{\"player\":0}"[..]).is_err());
+        expl
+    }
+
+    #[test]
+    fn test_solvers() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let mc = train(&mut OuterMCCFR::new(g.clone()), &g);
+        let cfr = train(&mut FlatCFR::new(FlatTree::from_game(&g)), &g);
+        assert!(cfr < 0.1);
+        assert!(mc < 1.0);
+    }
+}
@@ -1,11 +1,10 @@
 use crate::{ActionIndex, ActivePlayer, Categorical, Game, PlayerObservation};
 
 pub trait Strategy<G: Game> {
-    #[inline]
     fn policy(
         &self,
         active: &ActivePlayer<G>,
-        obs: &Vec<PlayerObservation<G>>,
+        obs: &[PlayerObservation<G>],
     ) -> Categorical<ActionIndex>;
 }
 
@@ -16,7 +15,7 @@ impl<G: Game> Strategy<G> for UniformStrategy {
     fn policy(
         &self,
         active: &ActivePlayer<G>,
-        _obs: &Vec<PlayerObservation<G>>,
+        _obs: &[PlayerObservation<G>],
     ) -> Categorical<ActionIndex> {
         if let ActivePlayer::Player(_p, ref actions) = active {
             Categorical::uniform((0..actions.len() as u32).collect::<Vec<_>>())
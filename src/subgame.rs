@@ -0,0 +1,538 @@
+use crate::{
+    public_state, ActionIndex, ActivePlayer, Categorical, Game, HistoryInfo, Observation,
+    OuterMCCFR, PlayerObservation, RegretStrategy, Strategy, Utility,
+};
+use hashbrown::HashMap;
+use rand::Rng;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Actions of the re-solving gadget: the initial chance choice of the root history,
+/// the opponent's choice between entering the subgame and taking the blueprint value,
+/// and the actions of the original game.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum GadgetAction<A> {
+    Root(usize),
+    Follow,
+    Terminate,
+    Game(A),
+}
+
+/// Observations of the re-solving gadget: the player's information set at the subgame root
+/// and all observations of one step of the original game.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum GadgetObservation<A, O>
+where
+    A: Clone + Hash + Debug + PartialEq + Eq,
+    O: Clone + Hash + Debug + PartialEq + Eq,
+{
+    Root(Vec<Observation<A, O>>),
+    Game(Vec<O>),
+}
+
+#[derive(Clone, Debug)]
+pub enum GadgetState<G: Game> {
+    /// The root history is being chosen.
+    Start,
+    /// The opponent decides whether to enter the subgame at the given root.
+    Root(usize),
+    /// Play continues in the original game.
+    Game(HistoryInfo<G>),
+}
+
+/// The re-solving gadget (Burch et al., 2014) of a public state of a two-player zero-sum game.
+///
+/// Chance picks a root history of the public state proportionally to the blueprint reach
+/// of everyone except the opponent. The opponent then either terminates and receives its
+/// blueprint counterfactual value of the information set, or follows into the subgame.
+/// Any equilibrium of the gadget is not more exploitable than the blueprint.
+///
+/// A depth-limited gadget (`depth_limited`) ends the subgame `depth` actions below the roots,
+/// the players then receive their blueprint expected utilities.
+#[derive(Clone, Debug)]
+pub struct SubgameGadget<G: Game> {
+    pub game: G,
+    /// The re-solving player.
+    pub player: usize,
+    pub roots: Vec<HistoryInfo<G>>,
+    /// Probability of each root in the initial chance node.
+    pub root_probs: Vec<f64>,
+    /// Opponent's (normalized) blueprint counterfactual value of the information set of each root.
+    pub root_values: Vec<Utility>,
+    /// Maximum number of actions played below the roots, `None` to play to the end.
+    pub depth: Option<usize>,
+    /// Blueprint utilities of the non-terminal histories at the depth limit.
+    pub leaf_values: HashMap<Vec<ActionIndex>, Vec<Utility>>,
+}
+
+impl<G: Game> SubgameGadget<G> {
+    /// Build the gadget for the public state with the given key, re-solving for `player`.
+    /// `blueprint` contains one strategy per player.
+    pub fn new(
+        game: &G,
+        blueprint: &[&dyn Strategy<G>],
+        key: &[PlayerObservation<G>],
+        player: usize,
+    ) -> Self {
+        Self::build(game, blueprint, key, player, None)
+    }
+
+    /// As `new`, but the subgame ends `depth` actions below the roots.
+    pub fn depth_limited(
+        game: &G,
+        blueprint: &[&dyn Strategy<G>],
+        key: &[PlayerObservation<G>],
+        player: usize,
+        depth: usize,
+    ) -> Self {
+        Self::build(game, blueprint, key, player, Some(depth))
+    }
+
+    fn build(
+        game: &G,
+        blueprint: &[&dyn Strategy<G>],
+        key: &[PlayerObservation<G>],
+        player: usize,
+        depth: Option<usize>,
+    ) -> Self {
+        assert_eq!(game.players(), 2, "re-solving requires a two-player game");
+        assert_eq!(blueprint.len(), 2);
+        let opponent = 1 - player;
+        let ps = public_state(game, key);
+        for &ri in &ps.roots {
+            assert_zero_sum(game, &ps.histories[ri]);
+        }
+        let mut roots = Vec::new();
+        let mut reaches = Vec::new();
+        let mut values = Vec::new();
+        for &ri in &ps.roots {
+            let h = &ps.histories[ri];
+            let r = reach_except(game, blueprint, h, opponent);
+            if r > 0.0 {
+                values.push(expected_utility(game, blueprint, h)[opponent]);
+                reaches.push(r);
+                roots.push(h.clone());
+            }
+        }
+        assert!(
+            !roots.is_empty(),
+            "public state not reachable by the blueprint"
+        );
+        // Counterfactual values are normalized by the infoset reach
+        let mut root_values = vec![0.0; roots.len()];
+        for i in 0..roots.len() {
+            let (mut v, mut r) = (0.0, 0.0);
+            for j in 0..roots.len() {
+                if roots[j].observations[opponent] == roots[i].observations[opponent] {
+                    v += reaches[j] * values[j];
+                    r += reaches[j];
+                }
+            }
+            root_values[i] = v / r;
+        }
+        let mut leaf_values = HashMap::new();
+        if let Some(d) = depth {
+            for h in &roots {
+                collect_leaves(game, blueprint, h.clone(), d, &mut leaf_values);
+            }
+        }
+        let total: f64 = reaches.iter().sum();
+        SubgameGadget {
+            game: game.clone(),
+            player,
+            roots,
+            root_probs: reaches.iter().map(|r| r / total).collect(),
+            root_values,
+            depth,
+            leaf_values,
+        }
+    }
+
+    /// The active player of the gadget in the game history `hist`, terminal at the depth limit.
+    fn game_active(&self, hist: &HistoryInfo<G>) -> ActivePlayer<Self> {
+        match self.leaf_values.get(&hist.history_indices) {
+            Some(u) => ActivePlayer::Terminal(u.clone()),
+            None => Self::wrap_active(&hist.active),
+        }
+    }
+
+    fn wrap_active(active: &ActivePlayer<G>) -> ActivePlayer<Self> {
+        match active {
+            ActivePlayer::Terminal(ref u) => ActivePlayer::Terminal(u.clone()),
+            ActivePlayer::Player(p, ref actions) => ActivePlayer::Player(
+                *p,
+                actions
+                    .iter()
+                    .map(|a| GadgetAction::Game(a.clone()))
+                    .collect(),
+            ),
+            ActivePlayer::Chance(ref d) => ActivePlayer::Chance(Categorical::new(
+                d.probs().clone(),
+                d.items()
+                    .iter()
+                    .map(|a| GadgetAction::Game(a.clone()))
+                    .collect::<Vec<_>>(),
+            )),
+        }
+    }
+}
+
+impl<G: Game> Game for SubgameGadget<G> {
+    type State = GadgetState<G>;
+    type Observation = GadgetObservation<G::Action, G::Observation>;
+    type Action = GadgetAction<G::Action>;
+
+    fn players(&self) -> usize {
+        2
+    }
+
+    fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+        let active = ActivePlayer::Chance(Categorical::new(
+            self.root_probs.clone(),
+            (0..self.roots.len())
+                .map(GadgetAction::Root)
+                .collect::<Vec<_>>(),
+        ));
+        (GadgetState::Start, active)
+    }
+
+    fn update_state(
+        &self,
+        hist: &HistoryInfo<Self>,
+        action: &Self::Action,
+    ) -> (
+        Self::State,
+        ActivePlayer<Self>,
+        Vec<Option<Self::Observation>>,
+    ) {
+        let opponent = 1 - self.player;
+        match (&hist.state, action) {
+            (GadgetState::Start, GadgetAction::Root(i)) => {
+                let mut obs: Vec<_> = self.roots[*i]
+                    .observations
+                    .iter()
+                    .map(|o| Some(GadgetObservation::Root(o.clone())))
+                    .collect();
+                obs[2] = None;
+                let active = ActivePlayer::Player(
+                    opponent as u32,
+                    vec![GadgetAction::Follow, GadgetAction::Terminate],
+                );
+                (GadgetState::Root(*i), active, obs)
+            }
+            (GadgetState::Root(i), GadgetAction::Follow) => {
+                let root = &self.roots[*i];
+                let active = self.game_active(root);
+                (GadgetState::Game(root.clone()), active, vec![None; 3])
+            }
+            (GadgetState::Root(i), GadgetAction::Terminate) => {
+                let v = self.root_values[*i];
+                let mut u = vec![-v; 2];
+                u[opponent] = v;
+                (
+                    GadgetState::Root(*i),
+                    ActivePlayer::Terminal(u),
+                    vec![None; 3],
+                )
+            }
+            (GadgetState::Game(h), GadgetAction::Game(a)) => {
+                let h2 = self.game.play_value(h, a);
+                let obs = h2
+                    .observations_since(h)
+                    .iter()
+                    .map(|os| {
+                        let os: Vec<_> = os
+                            .iter()
+                            .filter_map(|o| match o {
+                                Observation::Obs(o) => Some(o.clone()),
+                                Observation::Own(_) => None,
+                            })
+                            .collect();
+                        Some(os)
+                            .filter(|os| !os.is_empty())
+                            .map(GadgetObservation::Game)
+                    })
+                    .collect();
+                let active = self.game_active(&h2);
+                (GadgetState::Game(h2), active, obs)
+            }
+            (s, a) => panic!("invalid gadget action {:?} in state {:?}", a, s),
+        }
+    }
+}
+
+/// Panics if a terminal history below `hist` does not have zero-sum utilities.
+fn assert_zero_sum<G: Game>(game: &G, hist: &HistoryInfo<G>) {
+    if let ActivePlayer::Terminal(ref u) = hist.active {
+        assert!(
+            u.iter().sum::<Utility>().abs() < 1e-9,
+            "re-solving requires a zero-sum game, got utilities {:?}",
+            u
+        );
+    }
+    for ai in 0..hist.active.actions().len() {
+        assert_zero_sum(game, &game.play(hist, ai));
+    }
+}
+
+/// Reach probability of `hist` under `strategies`, excluding the contribution of `player`.
+fn reach_except<G: Game>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    hist: &HistoryInfo<G>,
+    player: usize,
+) -> f64 {
+    let mut h = game.start();
+    let mut reach = 1.0;
+    for &ai in &hist.history_indices {
+        match h.active {
+            ActivePlayer::Chance(ref d) => reach *= d.probs()[ai as usize],
+            ActivePlayer::Player(p, _) if p as usize != player => {
+                reach *= strategies[p as usize]
+                    .policy(&h.active, &h.observations[p as usize])
+                    .probs()[ai as usize]
+            }
+            _ => (),
+        }
+        h = game.play_owned(h, ai as usize);
+    }
+    reach
+}
+
+/// Blueprint utilities of the non-terminal histories `depth` actions below `hist`.
+fn collect_leaves<G: Game>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    hist: HistoryInfo<G>,
+    depth: usize,
+    leaves: &mut HashMap<Vec<ActionIndex>, Vec<Utility>>,
+) {
+    if let ActivePlayer::Terminal(_) = hist.active {
+        return;
+    }
+    if depth == 0 {
+        let u = expected_utility(game, strategies, &hist);
+        leaves.insert(hist.history_indices, u);
+        return;
+    }
+    for ai in 0..hist.active.actions().len() {
+        collect_leaves(game, strategies, game.play(&hist, ai), depth - 1, leaves);
+    }
+}
+
+/// Expected utilities of all players from `hist` on when playing `strategies`.
+fn expected_utility<G: Game>(
+    game: &G,
+    strategies: &[&dyn Strategy<G>],
+    hist: &HistoryInfo<G>,
+) -> Vec<Utility> {
+    let probs = match hist.active {
+        ActivePlayer::Terminal(ref u) => return u.clone(),
+        ActivePlayer::Chance(ref d) => d.probs().clone(),
+        ActivePlayer::Player(p, _) => strategies[p as usize]
+            .policy(&hist.active, &hist.observations[p as usize])
+            .probs()
+            .clone(),
+    };
+    let mut res = vec![0.0; game.players()];
+    for (ai, pr) in probs.iter().enumerate() {
+        if *pr > 0.0 {
+            let u = expected_utility(game, strategies, &game.play(hist, ai));
+            res.iter_mut().zip(u).for_each(|(r, v)| *r += pr * v);
+        }
+    }
+    res
+}
+
+/// The re-solved strategy of one player: plays the gadget strategy in the information sets
+/// of the subgame and falls back to the blueprint elsewhere.
+///
+/// The subgame information sets are those of the histories below the gadget roots (above the
+/// depth limit), i.e. the histories whose public state key extends the re-solved one. The
+/// player's observations are assumed to determine whether it is in the subgame.
+pub struct ResolvedStrategy<'a, G: Game> {
+    pub gadget: SubgameGadget<G>,
+    pub strategy: RegretStrategy<SubgameGadget<G>>,
+    pub blueprint: &'a dyn Strategy<G>,
+    /// The player's gadget observations of every subgame information set.
+    infosets: HashMap<Vec<PlayerObservation<G>>, Vec<PlayerObservation<SubgameGadget<G>>>>,
+}
+
+impl<'a, G: Game> ResolvedStrategy<'a, G> {
+    pub fn new(
+        gadget: SubgameGadget<G>,
+        strategy: RegretStrategy<SubgameGadget<G>>,
+        blueprint: &'a dyn Strategy<G>,
+    ) -> Self {
+        let mut rs = ResolvedStrategy {
+            gadget,
+            strategy,
+            blueprint,
+            infosets: HashMap::new(),
+        };
+        let start = rs.gadget.start();
+        rs.collect_infosets(start);
+        rs
+    }
+
+    fn collect_infosets(&mut self, gh: HistoryInfo<SubgameGadget<G>>) {
+        let p = self.gadget.player;
+        let n = match gh.state {
+            // Only follow into the subgame
+            GadgetState::Root(_) => gh.active.actions().len().min(1),
+            GadgetState::Game(ref h) => {
+                if gh.active.player() == Some(p) {
+                    self.infosets
+                        .insert(h.observations[p].clone(), gh.observations[p].clone());
+                }
+                gh.active.actions().len()
+            }
+            GadgetState::Start => gh.active.actions().len(),
+        };
+        for ai in 0..n {
+            let gh2 = self.gadget.play(&gh, ai);
+            self.collect_infosets(gh2);
+        }
+    }
+}
+
+impl<'a, G: Game> Strategy<G> for ResolvedStrategy<'a, G> {
+    fn policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        match self.infosets.get(obs) {
+            None => self.blueprint.policy(active, obs),
+            Some(gobs) => self
+                .strategy
+                .policy(&SubgameGadget::wrap_active(active), gobs),
+        }
+    }
+}
+
+/// Re-solve the public state with the given key for `player` by running outcome-sampling
+/// MCCFR on the re-solving gadget. `blueprint` contains one strategy per player.
+pub fn resolve_subgame<'a, G: Game, R: Rng>(
+    game: &G,
+    blueprint: &[&'a dyn Strategy<G>],
+    key: &[PlayerObservation<G>],
+    player: usize,
+    iterations: usize,
+    epsilon: f64,
+    rng: &mut R,
+) -> ResolvedStrategy<'a, G> {
+    let gadget = SubgameGadget::new(game, blueprint, key, player);
+    resolve_gadget(gadget, blueprint[player], iterations, epsilon, rng)
+}
+
+/// Re-solve a (e.g. depth-limited) gadget for its player, falling back to `blueprint`
+/// outside of the subgame.
+pub fn resolve_gadget<'a, G: Game, R: Rng>(
+    gadget: SubgameGadget<G>,
+    blueprint: &'a dyn Strategy<G>,
+    iterations: usize,
+    epsilon: f64,
+    rng: &mut R,
+) -> ResolvedStrategy<'a, G> {
+    let mut mc = OuterMCCFR::new(gadget.clone());
+    mc.compute_rng(iterations, epsilon, rng);
+    let strategy = mc.strategies.swap_remove(gadget.player);
+    ResolvedStrategy::new(gadget, strategy, blueprint)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{resolve_gadget, resolve_subgame, GadgetObservation, SubgameGadget};
+    use crate::{
+        exploitability, goofspiel, ActionIndex, ActivePlayer, Categorical, Game, Goofspiel,
+        MatrixGame, Observation::*, PlayerObservation, Sequentialized, Strategy, UniformStrategy,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    /// One strategy per player.
+    struct Seats<'a, G: Game>(Vec<&'a dyn Strategy<G>>);
+
+    impl<'a, G: Game> Strategy<G> for Seats<'a, G> {
+        fn policy(
+            &self,
+            active: &ActivePlayer<G>,
+            obs: &[PlayerObservation<G>],
+        ) -> Categorical<ActionIndex> {
+            self.0[active.player().expect("player node")].policy(active, obs)
+        }
+    }
+
+    #[test]
+    fn test_resolve_goofspiel() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        let mut rng = SmallRng::seed_from_u64(1);
+        let key = [Obs(goofspiel::Info::Card(2))];
+        let rs = resolve_subgame(&g, &[&u, &u], &key, 0, 5000, 0.6, &mut rng);
+        assert_eq!(rs.gadget.roots.len(), 1);
+        assert!(rs.gadget.root_values[0].abs() < 1e-9);
+        let s = g.play_owned(g.start(), 1);
+        let pol = rs.policy(&s.active, &s.observations[0]);
+        assert!(pol.probs()[1] > 0.8);
+        // Outside of the subgame the blueprint is used
+        let s = g.play_owned(g.start(), 0);
+        let pol = rs.policy(&s.active, &s.observations[0]);
+        assert_eq!(pol.probs(), &vec![1.0 / 3.0; 3]);
+        // The game observations pass through the gadget
+        let gh = [0, 0, 0, 0]
+            .iter()
+            .fold(rs.gadget.start(), |h, &ai| rs.gadget.play_owned(h, ai));
+        assert_eq!(
+            gh.observations[1].last(),
+            Some(&Obs(GadgetObservation::Game(vec![
+                goofspiel::Info::Winner(None)
+            ])))
+        );
+
+        // Re-solving for both players is not more exploitable than the blueprint
+        let rs1 = resolve_subgame(&g, &[&u, &u], &key, 1, 5000, 0.6, &mut rng);
+        let resolved = Seats(vec![&rs, &rs1]);
+        assert!(exploitability(&g, &resolved) <= exploitability(&g, &u) + 1e-2);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero-sum")]
+    fn test_not_zero_sum() {
+        let g = Sequentialized::new(MatrixGame::bimatrix(
+            &[vec![1.0, 0.0], vec![0.0, 1.0]],
+            &[vec![1.0, 0.0], vec![0.0, 1.0]],
+        ));
+        let u = UniformStrategy {};
+        SubgameGadget::new(&g, &[&u, &u], &[], 0);
+    }
+
+    #[test]
+    fn test_depth_limited() {
+        let g = Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let u = UniformStrategy {};
+        let key = [Obs(goofspiel::Info::Card(2))];
+        let gadget = SubgameGadget::depth_limited(&g, &[&u, &u], &key, 0, 2);
+        // Both bids of the first round
+        assert_eq!(gadget.leaf_values.len(), 9);
+        let rs = resolve_gadget(gadget, &u, 1000, 0.6, &mut SmallRng::seed_from_u64(1));
+        let s = g.play_owned(g.start(), 1);
+        let pol = rs.policy(&s.active, &s.observations[0]);
+        assert!((pol.probs().iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // Below the depth limit the blueprint is used
+        let s2 = g.play_owned(g.play_owned(g.play_owned(s.clone(), 2), 0), 0);
+        let pol = rs.policy(&s2.active, &s2.observations[0]);
+        assert_eq!(pol.probs(), &vec![0.5; 2]);
+
+        // A limit below the end of the game is the full re-solving
+        let deep = SubgameGadget::depth_limited(&g, &[&u, &u], &key, 0, 9);
+        assert!(deep.leaf_values.is_empty());
+        let rs = resolve_gadget(deep, &u, 1000, 0.6, &mut SmallRng::seed_from_u64(1));
+        let mut rng = SmallRng::seed_from_u64(1);
+        let full = resolve_subgame(&g, &[&u, &u], &key, 0, 1000, 0.6, &mut rng);
+        assert_eq!(
+            rs.policy(&s.active, &s.observations[0]),
+            full.policy(&s.active, &s.observations[0])
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{ActionIndex, ActivePlayer, Categorical, Game, HistoryInfo, Observation};
-use std::collections::HashMap;
-use std::fmt::Debug;
+use crate::{
+    ActionIndex, ActivePlayer, Categorical, Game, HistoryInfo, Observation, PlayerObservation,
+    Strategy, Utility,
+};
+use hashbrown::HashMap;
+use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::sync::Arc;
 
@@ -12,6 +15,8 @@ where
     players: usize,
     tree: Arc<TreeGameNode<Obs>>,
     obs_index: HashMap<Obs, usize>,
+    /// Reverse of `obs_index`.
+    obs_values: HashMap<usize, Obs>,
 }
 
 #[derive(Clone, Debug)]
@@ -21,7 +26,20 @@ where
 {
     active: ActivePlayer<TreeGame<Obs>>,
     children: Vec<Arc<TreeGameNode<Obs>>>,
-    observations: Vec<Option<usize>>,
+    /// Ids of all observations of every player (and the public observer) on entering the node.
+    observations: Vec<Option<Vec<usize>>>,
+    /// Names of the actions (children).
+    labels: Vec<String>,
+}
+
+impl<Obs> TreeGameNode<Obs>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+{
+    /// Names of the actions available in this node.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
 }
 
 impl<Obs> Game for TreeGame<Obs>
@@ -29,7 +47,8 @@ where
     Obs: Clone + Debug + PartialEq + Hash + Eq,
 {
     type State = Arc<TreeGameNode<Obs>>;
-    type Observation = usize;
+    /// Ids of all the observations of a player in one step, in order.
+    type Observation = Vec<usize>;
     type Action = ActionIndex;
 
     fn players(&self) -> usize {
@@ -58,30 +77,87 @@ impl<Obs> TreeGame<Obs>
 where
     Obs: Clone + Debug + PartialEq + Hash + Eq,
 {
+    /// Copy the whole tree of `game`. The action names (see `TreeGameNode::labels`) are the
+    /// `Debug` representations of the original actions, the observation ids map back to the
+    /// original observations with `observation`.
     pub fn from_game<G: Game<Observation = Obs>>(game: &G) -> Self {
         let mut obs_index: HashMap<G::Observation, usize> = HashMap::new();
+        let tree = traverse_game(
+            game,
+            &game.start(),
+            vec![None; game.players() + 1],
+            &mut obs_index,
+        );
+        Self::new(game.players(), tree, obs_index)
+    }
+
+    fn new(players: usize, tree: Arc<TreeGameNode<Obs>>, obs_index: HashMap<Obs, usize>) -> Self {
+        let obs_values = obs_index.iter().map(|(o, &i)| (i, o.clone())).collect();
         TreeGame {
-            players: game.players(),
-            tree: traverse_game(
-                game,
-                &game.start(),
-                vec![None; game.players() + 1],
-                &mut obs_index,
-            ),
+            players,
+            tree,
             obs_index,
+            obs_values,
         }
     }
+
+    /// Ids of the original observations.
+    pub fn obs_index(&self) -> &HashMap<Obs, usize> {
+        &self.obs_index
+    }
+
+    /// The original observation of the given id.
+    pub fn observation(&self, id: usize) -> Option<&Obs> {
+        self.obs_values.get(&id)
+    }
+
+    /// Observation sequence with the ids replaced by the original observations and the
+    /// action indices by the action names, e.g. for printing.
+    pub fn describe(
+        &self,
+        hist: &HistoryInfo<Self>,
+        player: usize,
+    ) -> Vec<Observation<String, Obs>> {
+        let mut node = &self.tree;
+        let mut res = Vec::new();
+        for &a in &hist.history_indices {
+            let child = &node.children[a as usize];
+            if node.active.player() == Some(player) {
+                res.push(Observation::Own(node.labels[a as usize].clone()));
+            }
+            if let Some(ref ids) = child.observations[player] {
+                res.extend(
+                    ids.iter()
+                        .map(|o| Observation::Obs(self.obs_values[o].clone())),
+                );
+            }
+            node = child;
+        }
+        res
+    }
+}
+
+/// The id of `obs`, assigning the next free id to new observations.
+fn intern<Obs: Hash + Eq + Clone>(obs_index: &mut HashMap<Obs, usize>, obs: &Obs) -> usize {
+    let len = obs_index.len();
+    *obs_index.entry(obs.clone()).or_insert(len)
 }
 
 fn traverse_game<Obs, G: Game<Observation = Obs>>(
     game: &G,
     hist: &HistoryInfo<G>,
-    last_obs: Vec<Option<usize>>,
+    last_obs: Vec<Option<Vec<usize>>>,
     obs_index: &mut HashMap<Obs, usize>,
 ) -> Arc<TreeGameNode<Obs>>
 where
     Obs: Clone + Debug + PartialEq + Hash + Eq,
 {
+    let labels = hist
+        .active
+        .actions()
+        .iter()
+        .map(|a| format!("{:?}", a))
+        .collect();
     let ch: Vec<_> = hist
         .active
         .actions()
@@ -89,16 +165,18 @@ where
         .enumerate()
         .map(|(i, _a)| {
             let h2 = game.play(hist, i);
-            let obs_len = obs_index.len();
             let obs = h2
                 .observations_since(hist)
                 .iter()
                 .map(|os| {
-                    if let Some(Observation::Obs(o)) = os.last() {
-                        Some(*obs_index.entry(o.clone()).or_insert(obs_len + 1))
-                    } else {
-                        None
-                    }
+                    let ids: Vec<usize> = os
+                        .iter()
+                        .filter_map(|o| match o {
+                            Observation::Obs(o) => Some(intern(obs_index, o)),
+                            Observation::Own(_) => None,
+                        })
+                        .collect();
+                    Some(ids).filter(|ids| !ids.is_empty())
                 })
                 .collect();
             traverse_game(game, &h2, obs, obs_index)
@@ -118,12 +196,491 @@ where
         active,
         children: ch,
         observations: last_obs,
+        labels,
     })
 }
 
+/// Index of a node in a `TreeGameBuilder`.
+pub type NodeId = usize;
+
+/// Explicit construction of a `TreeGame` node by node.
+///
+/// Nodes are created with `player`, `chance` and `terminal` and connected with named
+/// actions (`action`) or chance outcomes (`outcome`). Observations are attached to the node
+/// in which they are received (`observe`). Information sets of player nodes can be given
+/// directly as labels observed by the acting player (`info_set`); `build` checks that the
+/// nodes with the same label indeed form one information set. Observations in the
+/// root are ignored as all histories share them. `build` validates the tree.
+#[derive(Clone, Debug)]
+pub struct TreeGameBuilder<Obs>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+{
+    players: usize,
+    nodes: Vec<BuilderNode<Obs>>,
+}
+
+#[derive(Clone, Debug)]
+enum BuilderKind {
+    Player(u32),
+    Chance,
+    Terminal(Vec<Utility>),
+}
+
+#[derive(Clone, Debug)]
+struct BuilderNode<Obs> {
+    kind: BuilderKind,
+    /// Label, chance probability (for chance nodes) and target of every edge.
+    edges: Vec<(String, Option<f64>, NodeId)>,
+    /// Observing player (or `players` for the public observer) and the observation.
+    observations: Vec<(usize, Obs)>,
+    info_set: Option<Obs>,
+}
+
+/// Observation sequence of a player in the built tree.
+type BuilderObservations<Obs> = Vec<Observation<ActionIndex, Obs>>;
+
+/// A reason why a `TreeGameBuilder` does not describe a valid game tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TreeBuildError {
+    /// A referenced node does not exist.
+    InvalidNode(NodeId),
+    /// The node is the target of several edges (or the root is a target).
+    NotATree(NodeId),
+    /// The node is not reachable from the root.
+    Unreachable(NodeId),
+    /// Player index out of range in a node or an observation.
+    InvalidPlayer(NodeId),
+    /// A player or chance node without actions.
+    NoActions(NodeId),
+    /// An action added to a chance or terminal node, or an outcome to a non-chance node.
+    EdgeKind(NodeId),
+    DuplicateAction {
+        node: NodeId,
+        label: String,
+    },
+    /// Negative or non-finite chance probabilities or probabilities not summing to 1.
+    InvalidProbabilities(NodeId),
+    /// Terminal utilities not matching the number of players.
+    InvalidUtilities(NodeId),
+    /// Several observations of one player in a node (including the `info_set` label).
+    ConflictingObservation {
+        node: NodeId,
+        player: usize,
+    },
+    /// Two nodes with the same information set label differ in the player, in the actions
+    /// or in the earlier observations of the player (so they are not one information set).
+    InfoSetMismatch {
+        first: NodeId,
+        second: NodeId,
+    },
+}
+
+impl fmt::Display for TreeBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeBuildError::InvalidNode(n) => write!(f, "node {} does not exist", n),
+            TreeBuildError::NotATree(n) => write!(f, "node {} has more than one parent", n),
+            TreeBuildError::Unreachable(n) => write!(f, "node {} is not reachable", n),
+            TreeBuildError::InvalidPlayer(n) => write!(f, "node {}: invalid player", n),
+            TreeBuildError::NoActions(n) => write!(f, "node {} has no actions", n),
+            TreeBuildError::EdgeKind(n) => write!(f, "node {}: wrong kind of edge", n),
+            TreeBuildError::DuplicateAction { node, label } => {
+                write!(f, "node {}: duplicate action {:?}", node, label)
+            }
+            TreeBuildError::InvalidProbabilities(n) => {
+                write!(f, "node {}: invalid chance probabilities", n)
+            }
+            TreeBuildError::InvalidUtilities(n) => {
+                write!(f, "node {}: wrong number of utilities", n)
+            }
+            TreeBuildError::ConflictingObservation { node, player } => {
+                write!(f, "node {}: two observations for player {}", node, player)
+            }
+            TreeBuildError::InfoSetMismatch { first, second } => write!(
+                f,
+                "nodes {} and {} share an information set but differ",
+                first, second
+            ),
+        }
+    }
+}
+
+impl<Obs> TreeGameBuilder<Obs>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+{
+    pub fn new(players: usize) -> Self {
+        TreeGameBuilder {
+            players,
+            nodes: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, kind: BuilderKind) -> NodeId {
+        self.nodes.push(BuilderNode {
+            kind,
+            edges: Vec::new(),
+            observations: Vec::new(),
+            info_set: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// A new decision node of `player`.
+    pub fn player(&mut self, player: u32) -> NodeId {
+        self.add(BuilderKind::Player(player))
+    }
+
+    /// A new chance node, add its outcomes with `outcome`.
+    pub fn chance(&mut self) -> NodeId {
+        self.add(BuilderKind::Chance)
+    }
+
+    pub fn terminal(&mut self, utilities: Vec<Utility>) -> NodeId {
+        self.add(BuilderKind::Terminal(utilities))
+    }
+
+    fn edge(&mut self, parent: NodeId, label: &str, prob: Option<f64>, child: NodeId) -> &mut Self {
+        self.nodes[parent]
+            .edges
+            .push((label.to_string(), prob, child));
+        self
+    }
+
+    /// Add the action `label` leading from player node `parent` to `child`.
+    pub fn action(&mut self, parent: NodeId, label: &str, child: NodeId) -> &mut Self {
+        self.edge(parent, label, None, child)
+    }
+
+    /// Add the outcome `label` with probability `prob` leading from chance node `parent` to `child`.
+    pub fn outcome(&mut self, parent: NodeId, label: &str, prob: f64, child: NodeId) -> &mut Self {
+        self.edge(parent, label, Some(prob), child)
+    }
+
+    /// Player `player` observes `obs` when reaching `node`; use `players` for the public observer.
+    pub fn observe(&mut self, node: NodeId, player: usize, obs: Obs) -> &mut Self {
+        self.nodes[node].observations.push((player, obs));
+        self
+    }
+
+    /// Put the player node `node` into the information set `label`.
+    ///
+    /// The acting player observes `label` when reaching the node (except in the root). All the
+    /// nodes with the same label must have the same player, actions and earlier observations
+    /// of the player.
+    pub fn info_set(&mut self, node: NodeId, label: Obs) -> &mut Self {
+        self.nodes[node].info_set = Some(label);
+        self
+    }
+
+    /// Validate the tree rooted in `root` and build the game.
+    pub fn build(&self, root: NodeId) -> Result<TreeGame<Obs>, TreeBuildError> {
+        self.validate(root)?;
+        let mut obs_index = HashMap::new();
+        let tree = self.build_node(root, &mut obs_index);
+        Ok(TreeGame::new(self.players, tree, obs_index))
+    }
+
+    /// Observations received in `node` including the information set label.
+    fn node_observations(&self, node: NodeId) -> Result<Vec<Option<Obs>>, TreeBuildError> {
+        let n = &self.nodes[node];
+        let mut obs = vec![None; self.players + 1];
+        let label = match (&n.kind, &n.info_set) {
+            (BuilderKind::Player(p), Some(label)) => Some((*p as usize, label.clone())),
+            _ => None,
+        };
+        for (player, o) in n.observations.iter().cloned().chain(label) {
+            if player > self.players {
+                return Err(TreeBuildError::InvalidPlayer(node));
+            }
+            if obs[player].is_some() {
+                return Err(TreeBuildError::ConflictingObservation { node, player });
+            }
+            obs[player] = Some(o);
+        }
+        Ok(obs)
+    }
+
+    fn validate(&self, root: NodeId) -> Result<(), TreeBuildError> {
+        let mut parents = vec![0; self.nodes.len()];
+        if root >= self.nodes.len() {
+            return Err(TreeBuildError::InvalidNode(root));
+        }
+        parents[root] += 1;
+        let mut info_sets: HashMap<&Obs, NodeId> = HashMap::new();
+        for (i, n) in self.nodes.iter().enumerate() {
+            for (j, (label, prob, child)) in n.edges.iter().enumerate() {
+                if *child >= self.nodes.len() {
+                    return Err(TreeBuildError::InvalidNode(*child));
+                }
+                parents[*child] += 1;
+                if parents[*child] > 1 {
+                    return Err(TreeBuildError::NotATree(*child));
+                }
+                if n.edges[..j].iter().any(|e| &e.0 == label) {
+                    return Err(TreeBuildError::DuplicateAction {
+                        node: i,
+                        label: label.clone(),
+                    });
+                }
+                if matches!(n.kind, BuilderKind::Chance) != prob.is_some() {
+                    return Err(TreeBuildError::EdgeKind(i));
+                }
+            }
+            match n.kind {
+                BuilderKind::Player(p) => {
+                    if p as usize >= self.players {
+                        return Err(TreeBuildError::InvalidPlayer(i));
+                    }
+                    if n.edges.is_empty() {
+                        return Err(TreeBuildError::NoActions(i));
+                    }
+                    if let Some(ref label) = n.info_set {
+                        let first = *info_sets.entry(label).or_insert(i);
+                        let f = &self.nodes[first];
+                        let same_player = match f.kind {
+                            BuilderKind::Player(q) => q == p,
+                            _ => false,
+                        };
+                        let same_actions = f.edges.len() == n.edges.len()
+                            && f.edges.iter().zip(&n.edges).all(|(a, b)| a.0 == b.0);
+                        if !same_player || !same_actions {
+                            return Err(TreeBuildError::InfoSetMismatch { first, second: i });
+                        }
+                    }
+                }
+                BuilderKind::Chance => {
+                    if n.edges.is_empty() {
+                        return Err(TreeBuildError::NoActions(i));
+                    }
+                    let probs = n.edges.iter().filter_map(|e| e.1);
+                    // The sum is checked only for finite probabilities, so it is not NaN
+                    if !probs.clone().all(|p| p.is_finite() && p >= 0.0)
+                        || (probs.sum::<f64>() - 1.0).abs() > 1e-9
+                    {
+                        return Err(TreeBuildError::InvalidProbabilities(i));
+                    }
+                }
+                BuilderKind::Terminal(ref u) => {
+                    if !n.edges.is_empty() {
+                        return Err(TreeBuildError::EdgeKind(i));
+                    }
+                    if u.len() != self.players {
+                        return Err(TreeBuildError::InvalidUtilities(i));
+                    }
+                }
+            }
+        }
+        for i in 0..self.nodes.len() {
+            self.node_observations(i)?;
+        }
+        if parents[root] > 1 {
+            return Err(TreeBuildError::NotATree(root));
+        }
+        // With at most one parent per node, the unreachable nodes are exactly those
+        // not visited from the root (this includes all cycles).
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            reached[i] = true;
+            stack.extend(self.nodes[i].edges.iter().map(|e| e.2));
+        }
+        if let Some(i) = reached.iter().position(|r| !r) {
+            return Err(TreeBuildError::Unreachable(i));
+        }
+        let mut seqs = vec![Vec::new(); self.players + 1];
+        self.validate_info_sets(root, root, &mut seqs, &mut HashMap::new())
+    }
+
+    /// Check that the nodes sharing an information set label also share the observation
+    /// sequence of the acting player (as `TreeGame` records it).
+    fn validate_info_sets<'a>(
+        &'a self,
+        root: NodeId,
+        node: NodeId,
+        seqs: &mut Vec<BuilderObservations<Obs>>,
+        seen: &mut HashMap<&'a Obs, (NodeId, BuilderObservations<Obs>)>,
+    ) -> Result<(), TreeBuildError> {
+        let n = &self.nodes[node];
+        let lens: Vec<usize> = seqs.iter().map(|s| s.len()).collect();
+        if node != root {
+            let obs = self.node_observations(node)?;
+            for (s, o) in seqs.iter_mut().zip(obs) {
+                s.extend(o.map(Observation::Obs));
+            }
+        }
+        if let (BuilderKind::Player(p), Some(ref label)) = (&n.kind, &n.info_set) {
+            let seq = &seqs[*p as usize];
+            let (first, first_seq) = seen.entry(label).or_insert((node, seq.clone()));
+            if first_seq != seq {
+                return Err(TreeBuildError::InfoSetMismatch {
+                    first: *first,
+                    second: node,
+                });
+            }
+        }
+        for (j, e) in n.edges.iter().enumerate() {
+            if let BuilderKind::Player(p) = n.kind {
+                seqs[p as usize].push(Observation::Own(j as ActionIndex));
+            }
+            self.validate_info_sets(root, e.2, seqs, seen)?;
+            if let BuilderKind::Player(p) = n.kind {
+                seqs[p as usize].pop();
+            }
+        }
+        for (s, l) in seqs.iter_mut().zip(lens) {
+            s.truncate(l);
+        }
+        Ok(())
+    }
+
+    fn build_node(
+        &self,
+        node: NodeId,
+        obs_index: &mut HashMap<Obs, usize>,
+    ) -> Arc<TreeGameNode<Obs>> {
+        let n = &self.nodes[node];
+        let observations = self
+            .node_observations(node)
+            .expect("validated")
+            .into_iter()
+            .map(|o| o.map(|o| vec![intern(obs_index, &o)]))
+            .collect();
+        let indices = (0..n.edges.len() as ActionIndex).collect::<Vec<_>>();
+        let active = match n.kind {
+            BuilderKind::Player(p) => ActivePlayer::Player(p, indices),
+            BuilderKind::Chance => ActivePlayer::Chance(Categorical::new(
+                n.edges.iter().map(|e| e.1.unwrap()).collect::<Vec<_>>(),
+                indices,
+            )),
+            BuilderKind::Terminal(ref u) => ActivePlayer::Terminal(u.clone()),
+        };
+        Arc::new(TreeGameNode {
+            active,
+            children: n
+                .edges
+                .iter()
+                .map(|e| self.build_node(e.2, obs_index))
+                .collect(),
+            observations,
+            labels: n.edges.iter().map(|e| e.0.clone()).collect(),
+        })
+    }
+}
+
+/// A `TreeGame` observation sequence with every observation id separate, as in the
+/// original game.
+type FlatObservations = Vec<Observation<ActionIndex, usize>>;
+
+/// The tree observations and action names of the decision nodes of a player, keyed by the
+/// player's flattened observations.
+type InfoSetLabels<Obs> =
+    HashMap<FlatObservations, (Vec<PlayerObservation<TreeGame<Obs>>>, Vec<String>)>;
+
+/// A strategy computed on a `TreeGame` copy used to play the original game.
+///
+/// The observations of the original game are translated to the tree ids (grouped by steps
+/// as in the tree), own actions are matched by their `Debug` names. Panics on observations
+/// that do not occur in the tree.
+pub struct TreeStrategy<'a, Obs, S>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+    S: Strategy<TreeGame<Obs>>,
+{
+    tree: &'a TreeGame<Obs>,
+    strategy: &'a S,
+    labels: Vec<InfoSetLabels<Obs>>,
+}
+
+impl<'a, Obs, S> TreeStrategy<'a, Obs, S>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+    S: Strategy<TreeGame<Obs>>,
+{
+    pub fn new(tree: &'a TreeGame<Obs>, strategy: &'a S) -> Self {
+        let mut labels = vec![HashMap::new(); tree.players()];
+        Self::collect_labels(tree, &tree.start(), &mut labels);
+        TreeStrategy {
+            tree,
+            strategy,
+            labels,
+        }
+    }
+
+    fn collect_labels(
+        tree: &TreeGame<Obs>,
+        hist: &HistoryInfo<TreeGame<Obs>>,
+        labels: &mut Vec<InfoSetLabels<Obs>>,
+    ) {
+        if let Some(p) = hist.active.player() {
+            let flat = hist.observations[p]
+                .iter()
+                .flat_map(|o| match o {
+                    Observation::Obs(ids) => ids.iter().map(|&i| Observation::Obs(i)).collect(),
+                    Observation::Own(a) => vec![Observation::Own(*a)],
+                })
+                .collect();
+            labels[p]
+                .entry(flat)
+                .or_insert_with(|| (hist.observations[p].clone(), hist.state.labels.clone()));
+        }
+        for i in 0..hist.active.actions().len() {
+            Self::collect_labels(tree, &tree.play(hist, i), labels);
+        }
+    }
+}
+
+impl<'a, Obs, S, G> Strategy<G> for TreeStrategy<'a, Obs, S>
+where
+    Obs: Clone + Debug + PartialEq + Hash + Eq,
+    S: Strategy<TreeGame<Obs>>,
+    G: Game<Observation = Obs>,
+{
+    fn policy(
+        &self,
+        active: &ActivePlayer<G>,
+        obs: &[PlayerObservation<G>],
+    ) -> Categorical<ActionIndex> {
+        let (p, n) = match active {
+            ActivePlayer::Player(p, ref actions) => (*p, actions.len()),
+            _ => panic!("strategy requested for non-player state {:?}", active),
+        };
+        let mut tobs = Vec::with_capacity(obs.len());
+        for o in obs {
+            let t = match o {
+                Observation::Obs(o) => Observation::Obs(self.tree.obs_index[o]),
+                Observation::Own(a) => {
+                    let name = format!("{:?}", a);
+                    let labels = &self.labels[p as usize][&tobs].1;
+                    let i = labels
+                        .iter()
+                        .position(|l| l == &name)
+                        .unwrap_or_else(|| panic!("action {} not found in the tree game", name));
+                    Observation::Own(i as ActionIndex)
+                }
+            };
+            tobs.push(t);
+        }
+        let tobs = &self.labels[p as usize]
+            .get(&tobs)
+            .expect("observations not found in the tree game")
+            .0;
+        let tactive = ActivePlayer::Player(p, (0..n as ActionIndex).collect());
+        self.strategy.policy(&tactive, tobs)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{goofspiel, ActivePlayer, Game, TreeGame};
+    use super::TreeBuildError;
+    use crate::goofspiel::Info;
+    use crate::Observation::*;
+    use crate::{
+        check_perfect_recall, check_same_infosets, goofspiel, ActivePlayer, Categorical, Game,
+        HistoryInfo, LazyTreeGame, OuterMCCFR, Strategy, TreeGame, TreeGameBuilder, TreeStrategy,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
 
     #[test]
     fn treegame_goofspiel() {
@@ -140,4 +697,199 @@ mod test {
             panic!("expected terminal node");
         }
     }
+
+    #[test]
+    fn treegame_labels() {
+        let g = goofspiel::Goofspiel::new(3, goofspiel::Scoring::ZeroSum);
+        let t = TreeGame::from_game(&g);
+        let mut h = t.start();
+        for ai in &[1, 2, 0, 0] {
+            h = t.play_owned(h, *ai);
+        }
+        assert_eq!(h.state.labels(), &["1", "2"]);
+        assert_eq!(
+            t.describe(&h, 0),
+            vec![
+                Obs(Info::Card(2)),
+                Own("3".to_string()),
+                Obs(Info::Winner(Some(0))),
+                Obs(Info::Card(1))
+            ]
+        );
+        let card = t.obs_index()[&Info::Card(2)];
+        assert_eq!(t.observation(card), Some(&Info::Card(2)));
+
+        // Strategy trained on the tree plays the original game
+        let mut mc = OuterMCCFR::new(t.clone());
+        mc.compute_rng(200, 0.6, &mut SmallRng::seed_from_u64(1));
+        let ts = TreeStrategy::new(&t, &mc);
+        let mut gh = g.start();
+        for ai in &[1, 2, 0, 0] {
+            gh = g.play_owned(gh, *ai);
+        }
+        assert_eq!(
+            Strategy::<goofspiel::Goofspiel>::policy(&ts, &gh.active, &gh.observations[0]),
+            mc.policy(&h.active, &h.observations[0])
+        );
+    }
+
+    /// A card from 0..4 is dealt to the single player, who observes its parity and its
+    /// half in the same step, then guesses the parity.
+    #[derive(Clone, Debug)]
+    struct TwoHints;
+
+    impl Game for TwoHints {
+        type State = Option<u32>;
+        type Observation = u32;
+        type Action = u32;
+
+        fn players(&self) -> usize {
+            1
+        }
+
+        fn initial_state(&self) -> (Self::State, ActivePlayer<Self>) {
+            let dist = Categorical::uniform(vec![0, 1, 2, 3]);
+            (None, ActivePlayer::Chance(dist))
+        }
+
+        fn update_state(
+            &self,
+            hist: &HistoryInfo<Self>,
+            action: &Self::Action,
+        ) -> (Self::State, ActivePlayer<Self>, Vec<Option<u32>>) {
+            match hist.state {
+                None => (
+                    Some(*action),
+                    ActivePlayer::Player(0, vec![0, 1]),
+                    vec![None; 2],
+                ),
+                Some(card) => {
+                    let u = if card % 2 == *action { 1.0 } else { 0.0 };
+                    (hist.state, ActivePlayer::Terminal(vec![u]), vec![None; 2])
+                }
+            }
+        }
+
+        fn play_owned(&self, hist: HistoryInfo<Self>, action_index: usize) -> HistoryInfo<Self> {
+            let action = hist.active.actions()[action_index];
+            let (state, active, _) = self.update_state(&hist, &action);
+            let mut h = hist;
+            if let Some(p) = h.active.player() {
+                h.observations[p].push(Own(action));
+            } else {
+                h.observations[0].extend(vec![Obs(action % 2), Obs(10 + action / 2)]);
+            }
+            h.history.push(action);
+            h.history_indices.push(action_index as u32);
+            HistoryInfo { state, active, ..h }
+        }
+    }
+
+    #[test]
+    fn treegame_step_observations() {
+        let t = TreeGame::from_game(&TwoHints);
+        assert_eq!(check_same_infosets(&TwoHints, &t), Ok(()));
+        assert_eq!(
+            check_same_infosets(&TwoHints, &LazyTreeGame::new(t.clone())),
+            Ok(())
+        );
+        let h = t.play_owned(t.start(), 3);
+        let ids = vec![t.obs_index()[&1], t.obs_index()[&11]];
+        assert_eq!(h.observations[0], vec![Obs(ids)]);
+        assert_eq!(t.describe(&h, 0), vec![Obs(1), Obs(11)]);
+
+        let mut mc = OuterMCCFR::new(t.clone());
+        mc.compute_rng(200, 0.6, &mut SmallRng::seed_from_u64(1));
+        let ts = TreeStrategy::new(&t, &mc);
+        let gh = TwoHints.play_owned(TwoHints.start(), 3);
+        assert_eq!(
+            Strategy::<TwoHints>::policy(&ts, &gh.active, &gh.observations[0]),
+            mc.policy(&h.active, &h.observations[0])
+        );
+    }
+
+    #[test]
+    fn treegame_builder() {
+        // One-card poker: chance deals J/K to player 0, who bets or checks,
+        // player 1 calls or folds after a bet without seeing the card.
+        let mut b = TreeGameBuilder::new(2);
+        let root = b.chance();
+        for card in &["J", "K"] {
+            let p0 = b.player(0);
+            b.outcome(root, card, 0.5, p0).observe(p0, 0, *card);
+            let win = if *card == "K" { 1.0 } else { -1.0 };
+            let t = b.terminal(vec![win, -win]);
+            b.action(p0, "check", t);
+            let p1 = b.player(1);
+            b.action(p0, "bet", p1).info_set(p1, "bet");
+            let t = b.terminal(vec![2.0 * win, -2.0 * win]);
+            b.action(p1, "call", t);
+            let t = b.terminal(vec![1.0, -1.0]);
+            b.action(p1, "fold", t);
+        }
+        let g = b.build(root).unwrap();
+        assert!(check_perfect_recall(&g).is_ok());
+        let h = g.play_owned(g.play_owned(g.start(), 1), 1);
+        assert_eq!(h.state.labels(), &["call", "fold"]);
+        assert_eq!(h.observations[1].len(), 1);
+        assert_eq!(h.active.player(), Some(1));
+        let h2 = g.play_owned(g.play_owned(g.start(), 0), 1);
+        assert_eq!(h.observations[1], h2.observations[1]);
+        assert_ne!(h.observations[0], h2.observations[0]);
+
+        // Invalid trees
+        let mut b = TreeGameBuilder::<&str>::new(2);
+        let root = b.chance();
+        let t = b.terminal(vec![0.0, 0.0]);
+        b.outcome(root, "a", 0.3, t);
+        assert_eq!(
+            b.build(root).unwrap_err(),
+            TreeBuildError::InvalidProbabilities(0)
+        );
+        b.outcome(root, "b", 0.7, t);
+        assert_eq!(b.build(root).unwrap_err(), TreeBuildError::NotATree(t));
+        for probs in &[[f64::NAN, 1.0], [1.5, -0.5], [f64::INFINITY, 0.0]] {
+            let mut b = TreeGameBuilder::<&str>::new(2);
+            let root = b.chance();
+            for (label, &p) in ["a", "b"].iter().zip(probs) {
+                let t = b.terminal(vec![0.0, 0.0]);
+                b.outcome(root, label, p, t);
+            }
+            assert_eq!(
+                b.build(root).unwrap_err(),
+                TreeBuildError::InvalidProbabilities(0)
+            );
+        }
+        let mut b = TreeGameBuilder::new(2);
+        let (n0, n1) = (b.player(0), b.player(1));
+        b.action(n0, "x", n1).info_set(n0, "i").info_set(n1, "i");
+        let t = b.terminal(vec![0.0, 0.0]);
+        b.action(n1, "x", t);
+        assert_eq!(
+            b.build(n0).unwrap_err(),
+            TreeBuildError::InfoSetMismatch {
+                first: 0,
+                second: 1
+            }
+        );
+        // The same label after different observations of the player
+        let mut b = TreeGameBuilder::new(2);
+        let root = b.chance();
+        for (card, label) in &[("J", "i"), ("K", "i"), ("Q", "j")] {
+            let (p1, p0) = (b.player(1), b.player(0));
+            b.outcome(root, card, 1.0 / 3.0, p1);
+            b.action(p1, "y", p0).info_set(p0, *label);
+            let t = b.terminal(vec![0.0, 0.0]);
+            b.action(p0, "x", t);
+        }
+        assert!(b.build(root).is_ok());
+        b.observe(4, 0, "K");
+        assert_eq!(
+            b.build(root).unwrap_err(),
+            TreeBuildError::InfoSetMismatch {
+                first: 2,
+                second: 5
+            }
+        );
+    }
 }